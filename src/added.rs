@@ -0,0 +1,104 @@
+use csv::{ReaderBuilder, WriterBuilder};
+use std::collections::hash_map::{Entry, HashMap};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Name of the file inside the data dir that remembers when each card showed up
+const ADDED_FILE: &str = "added.csv";
+
+// Day each card was first seen, for queries like "added:7d". A CSV deck doesn't say when a card
+// was written, so the first time a session reads the card from its deck counts.
+#[derive(Debug, Default)]
+pub struct AddedStore {
+    path: Option<PathBuf>, // No path means the store only lives in memory
+    cards: HashMap<(String, String), u64>,
+    changed: bool,
+}
+
+impl AddedStore {
+    // Load the store from the data dir, a missing file just means nothing was seen yet
+    pub fn load(data_dir: &Path) -> Self {
+        let path = data_dir.join(ADDED_FILE);
+        let mut store = AddedStore { path: Some(path.clone()), ..AddedStore::default() };
+
+        let Ok(mut rdr) = ReaderBuilder::new().delimiter(b';').from_path(&path) else {
+            return store;
+        };
+        for record in rdr.records().filter_map(|r| r.ok()) {
+            let field = |i: usize| record.get(i).unwrap_or_default();
+            if let Ok(day) = field(2).parse() {
+                store.cards.insert((field(0).to_string(), field(1).to_string()), day);
+            }
+        }
+        store
+    }
+
+    // Write the store back if cards were noted since loading it
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let Some(path) = self.path.as_ref().filter(|_| self.changed) else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut wtr = WriterBuilder::new().delimiter(b';').from_path(path)?;
+        wtr.write_record(["deck", "card", "added"])?;
+        // Sorted so the file doesn't reshuffle on every save
+        let mut keys: Vec<&(String, String)> = self.cards.keys().collect();
+        keys.sort();
+        for key in keys {
+            wtr.write_record([key.0.as_str(), key.1.as_str(), &self.cards[key].to_string()])?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn get(&self, deck: &str, card: &str) -> Option<u64> {
+        self.cards.get(&(deck.to_string(), card.to_string())).copied()
+    }
+
+    // Move the days of every card of a deck to a new deck key, e.g. after renaming the file
+    pub fn rename_deck(&mut self, old: &str, new: &str) {
+        let moved: Vec<(String, String)> = self.cards.keys().filter(|(deck, _)| deck == old).cloned().collect();
+        for key in moved {
+            if let Some(day) = self.cards.remove(&key) {
+                self.cards.insert((new.to_string(), key.1), day);
+                self.changed = true;
+            }
+        }
+    }
+
+    // Remember today as the day of every card that wasn't seen before
+    pub fn note(&mut self, deck: &str, card: &str, today: u64) {
+        if let Entry::Vacant(entry) = self.cards.entry((deck.to_string(), card.to_string())) {
+            entry.insert(today);
+            self.changed = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_day_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = AddedStore::load(dir.path());
+        store.note("deck", "What is Rust?", 10);
+        store.note("deck", "What is Rust?", 12);
+        store.save().unwrap();
+
+        let mut store = AddedStore::load(dir.path());
+        assert_eq!(store.get("deck", "What is Rust?"), Some(10));
+        assert_eq!(store.get("deck", "What is Cargo?"), None);
+
+        // Nothing new, nothing written
+        fs::remove_file(dir.path().join(ADDED_FILE)).unwrap();
+        store.note("deck", "What is Rust?", 14);
+        store.save().unwrap();
+        assert!(!dir.path().join(ADDED_FILE).exists());
+    }
+}
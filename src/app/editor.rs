@@ -0,0 +1,470 @@
+use crossterm::event::KeyCode;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    Frame,
+};
+use trashcards::file_handler::{self, CsvStyle};
+use trashcards::progress::ProgressStore;
+
+// Single line text input with a cursor, for cell texts and column names
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextInput {
+    pub text: String,
+    cursor: usize, // Position in chars, not bytes
+}
+
+impl TextInput {
+    // Start with the cursor after the given text
+    pub fn new(text: &str) -> Self {
+        TextInput { text: text.to_string(), cursor: text.chars().count() }
+    }
+
+    // Apply an editing key, Enter and Esc are left to the caller
+    pub fn handle_keypress(&mut self, key: KeyCode) {
+        let len = self.text.chars().count();
+        match key {
+            KeyCode::Char(c) => {
+                self.text.insert(self.byte_index(), c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.text.remove(self.byte_index());
+            }
+            KeyCode::Delete if self.cursor < len => {
+                self.text.remove(self.byte_index());
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(len),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = len,
+            _ => {}
+        }
+    }
+
+    fn byte_index(&self) -> usize {
+        self.text.char_indices().nth(self.cursor).map(|(i, _)| i).unwrap_or(self.text.len())
+    }
+
+    // The text with a bar where the cursor is
+    pub fn display(&self) -> String {
+        let mut text = self.text.clone();
+        text.insert(self.byte_index(), '|');
+        text
+    }
+}
+
+// What the text input changes once Enter is pressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditTarget {
+    Cell,
+    Header,
+    NewColumn,
+}
+
+// Contents of the editor before a change, for undo
+#[derive(Debug, Clone)]
+struct EditorState {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    origins: Vec<Option<String>>,
+    row: usize,
+    col: usize,
+}
+
+// Table editor for a whole deck: cards are rows, columns are the sides of the cards
+pub struct DeckEditor {
+    pub is_open: bool,
+    pub path: String, // Deck file, also its key in the progress store
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    origins: Vec<Option<String>>, // Question of each row when it was last saved, None for new cards
+    style: CsvStyle,              // Delimiter and quoting of the file, kept when saving
+    pub row: usize,
+    pub col: usize,
+    pub input: Option<(EditTarget, TextInput)>,
+    pub dirty: bool, // Changes that aren't saved yet
+    pub message: Option<String>,
+    confirm_close: bool, // Esc was pressed once with unsaved changes
+    data_dir: Option<PathBuf>, // Progress of renamed questions is moved along in here
+    undo: Vec<EditorState>,
+    redo: Vec<EditorState>,
+}
+
+impl DeckEditor {
+    pub fn new(path: &str, headers: Vec<String>, rows: Vec<Vec<String>>, style: CsvStyle) -> Self {
+        DeckEditor {
+            is_open: true,
+            path: path.to_string(),
+            origins: rows.iter().map(|row| row.first().cloned()).collect(),
+            headers,
+            rows,
+            style,
+            row: 0,
+            col: 0,
+            input: None,
+            dirty: false,
+            message: None,
+            confirm_close: false,
+            data_dir: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    // Load a deck file for editing
+    pub fn open(path: &str, data_dir: Option<PathBuf>) -> Result<Self, Box<dyn Error>> {
+        let ((headers, rows), style) = file_handler::read_deck_with_style(path)?;
+        Ok(DeckEditor { data_dir, ..Self::new(path, headers, rows, style) })
+    }
+
+    pub fn handle_keypress(&mut self, key: KeyCode) {
+        if self.input.is_some() {
+            self.handle_input_keypress(key);
+            return;
+        }
+
+        let rows = self.rows.len();
+        let cols = self.headers.len();
+        if key != KeyCode::Esc {
+            self.confirm_close = false;
+        }
+        match key {
+            KeyCode::Down if rows > 0 => self.row = (self.row + 1).min(rows - 1),
+            KeyCode::Up => self.row = self.row.saturating_sub(1),
+            KeyCode::Right if cols > 0 => self.col = (self.col + 1).min(cols - 1),
+            KeyCode::Left => self.col = self.col.saturating_sub(1),
+            // Enter edits the selected cell
+            KeyCode::Enter if rows > 0 && cols > 0 => {
+                let text = self.rows[self.row].get(self.col).cloned().unwrap_or_default();
+                self.input = Some((EditTarget::Cell, TextInput::new(&text)));
+            }
+            // 'a' adds a card below the selected one and starts with its question
+            KeyCode::Char('a') => {
+                self.checkpoint();
+                let at = if rows == 0 { 0 } else { self.row + 1 };
+                self.rows.insert(at, vec![String::new(); cols.max(1)]);
+                self.origins.insert(at, None);
+                self.row = at;
+                self.col = 0;
+                self.dirty = true;
+                self.input = Some((EditTarget::Cell, TextInput::default()));
+            }
+            // 'd' or Delete removes the selected card
+            KeyCode::Char('d') | KeyCode::Delete if rows > 0 => {
+                self.checkpoint();
+                self.rows.remove(self.row);
+                self.origins.remove(self.row);
+                self.row = self.row.min(self.rows.len().saturating_sub(1));
+                self.dirty = true;
+            }
+            // 'K' and 'J' move the selected card up and down
+            KeyCode::Char('K') if self.row > 0 => self.swap_rows(self.row - 1),
+            KeyCode::Char('J') if self.row + 1 < rows => self.swap_rows(self.row + 1),
+            // 'r' renames the selected column, 'A' adds one after it
+            KeyCode::Char('r') if cols > 0 => {
+                self.input = Some((EditTarget::Header, TextInput::new(&self.headers[self.col])));
+            }
+            KeyCode::Char('A') => self.input = Some((EditTarget::NewColumn, TextInput::default())),
+            // 'D' deletes the selected column, a deck keeps at least one
+            KeyCode::Char('D') if cols > 1 => {
+                self.checkpoint();
+                self.headers.remove(self.col);
+                for row in self.rows.iter_mut().filter(|row| row.len() > self.col) {
+                    row.remove(self.col);
+                }
+                self.col = self.col.min(self.headers.len() - 1);
+                self.dirty = true;
+            }
+            // 'H' and 'L' move the selected column left and right
+            KeyCode::Char('H') if self.col > 0 => self.swap_columns(self.col - 1),
+            KeyCode::Char('L') if self.col + 1 < cols => self.swap_columns(self.col + 1),
+            // 'u' undoes the last change, Ctrl-r redoes it
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('s') => {
+                self.message = Some(match self.save() {
+                    Ok(()) => format!("Saved {} cards", self.rows.len()),
+                    Err(err) => format!("Error saving: {}", err),
+                });
+            }
+            // Esc closes the editor, unsaved changes need a second Esc
+            KeyCode::Esc => {
+                if self.dirty && !self.confirm_close {
+                    self.confirm_close = true;
+                    self.message = Some(String::from("Unsaved changes: 's' saves them, Esc again throws them away"));
+                } else {
+                    self.is_open = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_input_keypress(&mut self, key: KeyCode) {
+        let Some((target, ref mut input)) = self.input else {
+            return;
+        };
+        match key {
+            KeyCode::Esc => self.input = None,
+            KeyCode::Enter => {
+                let text = input.text.clone();
+                self.input = None;
+                self.checkpoint();
+                match target {
+                    EditTarget::Cell => *self.cell_mut(self.row, self.col) = text,
+                    EditTarget::Header => self.headers[self.col] = text,
+                    EditTarget::NewColumn => {
+                        let at = if self.headers.is_empty() { 0 } else { self.col + 1 };
+                        self.headers.insert(at, text);
+                        for row in self.rows.iter_mut() {
+                            if row.len() >= at {
+                                row.insert(at, String::new());
+                            }
+                        }
+                        self.col = at;
+                    }
+                }
+                self.dirty = true;
+            }
+            _ => input.handle_keypress(key),
+        }
+    }
+
+    // A cell of a card, rows that are shorter than the header get filled up
+    fn cell_mut(&mut self, row: usize, col: usize) -> &mut String {
+        let row = &mut self.rows[row];
+        if row.len() <= col {
+            row.resize(col + 1, String::new());
+        }
+        &mut row[col]
+    }
+
+    fn swap_rows(&mut self, other: usize) {
+        self.checkpoint();
+        self.rows.swap(self.row, other);
+        self.origins.swap(self.row, other);
+        self.row = other;
+        self.dirty = true;
+    }
+
+    fn swap_columns(&mut self, other: usize) {
+        self.checkpoint();
+        let needed = self.col.max(other) + 1;
+        self.headers.swap(self.col, other);
+        for row in self.rows.iter_mut() {
+            if row.len() < needed {
+                row.resize(needed, String::new());
+            }
+            row.swap(self.col, other);
+        }
+        self.col = other;
+        self.dirty = true;
+    }
+
+    fn state(&self) -> EditorState {
+        EditorState { headers: self.headers.clone(), rows: self.rows.clone(), origins: self.origins.clone(), row: self.row, col: self.col }
+    }
+
+    // Remember the contents before a change, a new change drops what was undone
+    fn checkpoint(&mut self) {
+        let state = self.state();
+        self.undo.push(state);
+        self.redo.clear();
+    }
+
+    fn restore(&mut self, state: EditorState) {
+        EditorState { headers: self.headers, rows: self.rows, origins: self.origins, row: self.row, col: self.col } = state;
+        self.dirty = true;
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(state) = self.undo.pop() {
+            self.redo.push(self.state());
+            self.restore(state);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(state) = self.redo.pop() {
+            self.undo.push(self.state());
+            self.restore(state);
+        }
+    }
+
+    // Write the deck back in its own style. Cards whose question changed keep their progress.
+    pub fn save(&mut self) -> Result<(), Box<dyn Error>> {
+        file_handler::write_csv_with_style(Path::new(&self.path), &self.headers, &self.rows, self.style)?;
+
+        let questions: Vec<Option<String>> = self.rows.iter().map(|row| row.first().cloned()).collect();
+        if let Some(ref data_dir) = self.data_dir {
+            let mut store = ProgressStore::load(data_dir);
+            for (old, new) in self.origins.iter().zip(questions.iter()) {
+                if let (Some(old), Some(new)) = (old, new) {
+                    if old != new {
+                        store.rename_card(&self.path, old, new);
+                    }
+                }
+            }
+            store.save()?;
+        }
+        self.origins = questions;
+        self.dirty = false;
+        Ok(())
+    }
+
+    // Function to draw the editor as the main content
+    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, size: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(4)].as_ref())
+            .split(size);
+
+        let selected = Style::default().add_modifier(Modifier::REVERSED);
+        let header = Row::new(self.headers.iter().enumerate().map(|(i, name)| {
+            let cell = Cell::from(name.clone());
+            if i == self.col { cell.style(Style::default().fg(Color::Yellow)) } else { cell }
+        })).style(Style::default().add_modifier(Modifier::BOLD));
+        let rows: Vec<Row> = self.rows.iter().enumerate().map(|(r, row)| {
+            Row::new((0..self.headers.len()).map(|c| {
+                let cell = Cell::from(row.get(c).cloned().unwrap_or_default());
+                if r == self.row && c == self.col { cell.style(selected) } else { cell }
+            }))
+        }).collect();
+
+        let name = Path::new(&self.path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let title = format!("Editing {} ({} cards){}", name, self.rows.len(), if self.dirty { ", not saved" } else { "" });
+        let widths = vec![Constraint::Ratio(1, self.headers.len().max(1) as u32); self.headers.len()];
+        let table = Table::new(rows)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .widths(&widths)
+            .column_spacing(1)
+            .highlight_style(Style::default().fg(Color::Yellow));
+        let mut state = TableState::default();
+        if !self.rows.is_empty() {
+            state.select(Some(self.row));
+        }
+        f.render_stateful_widget(table, chunks[0], &mut state);
+
+        // Text input while editing, otherwise the result of the last action or the keys
+        let (title, text) = match self.input {
+            Some((EditTarget::Cell, ref input)) => ("Card text (Enter: keep, Esc: cancel)", input.display()),
+            Some((EditTarget::Header, ref input)) => ("Column name (Enter: keep, Esc: cancel)", input.display()),
+            Some((EditTarget::NewColumn, ref input)) => ("New column name (Enter: add, Esc: cancel)", input.display()),
+            None => ("", self.message.clone().unwrap_or_else(|| {
+                String::from("Enter: edit, a/d: add/delete card, K/J: move card, s: save, Esc: close\nA/D: add/delete column, r: rename column, H/L: move column, u/Ctrl-r: undo/redo")
+            })),
+        };
+        f.render_widget(Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(title)), chunks[1]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn type_text(editor: &mut DeckEditor, text: &str) {
+        for c in text.chars() {
+            editor.handle_keypress(KeyCode::Char(c));
+        }
+    }
+
+    fn type_text_input(input: &mut TextInput, text: &str) {
+        for c in text.chars() {
+            input.handle_keypress(KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn test_text_input_cursor() {
+        let mut input = TextInput::new("maison");
+        input.handle_keypress(KeyCode::Home);
+        type_text_input(&mut input, "la ");
+        input.handle_keypress(KeyCode::End);
+        input.handle_keypress(KeyCode::Backspace);
+        input.handle_keypress(KeyCode::Left);
+        input.handle_keypress(KeyCode::Delete);
+        assert_eq!(input.text, "la mais");
+        assert_eq!(input.display(), "la mais|");
+    }
+
+    #[test]
+    fn test_edit_cards_and_columns() {
+        let mut editor = DeckEditor::new(
+            "deck.csv",
+            vec![String::from("French"), String::from("English")],
+            vec![vec![String::from("La maison"), String::from("The house")]],
+            CsvStyle::default(),
+        );
+
+        // Add a card, its question input opens right away
+        editor.handle_keypress(KeyCode::Char('a'));
+        type_text(&mut editor, "Le chien");
+        editor.handle_keypress(KeyCode::Enter);
+        editor.handle_keypress(KeyCode::Right);
+        editor.handle_keypress(KeyCode::Enter);
+        type_text(&mut editor, "The dog");
+        editor.handle_keypress(KeyCode::Enter);
+        assert_eq!(editor.rows[1], vec!["Le chien", "The dog"]);
+
+        // Move it to the top, then add a German column in front of English
+        editor.handle_keypress(KeyCode::Char('K'));
+        assert_eq!(editor.rows[0][0], "Le chien");
+        editor.handle_keypress(KeyCode::Left);
+        editor.handle_keypress(KeyCode::Char('A'));
+        type_text(&mut editor, "German");
+        editor.handle_keypress(KeyCode::Enter);
+        editor.handle_keypress(KeyCode::Char('L'));
+        assert_eq!(editor.headers, vec!["French", "English", "German"]);
+        assert_eq!(editor.rows[0], vec!["Le chien", "The dog", ""]);
+
+        // Delete the column and the card again
+        editor.handle_keypress(KeyCode::Char('D'));
+        editor.handle_keypress(KeyCode::Char('d'));
+        assert_eq!(editor.headers, vec!["French", "English"]);
+        assert_eq!(editor.rows, vec![vec!["La maison", "The house"]]);
+        assert!(editor.dirty);
+
+        // Undo brings the card back, redo deletes it again
+        editor.handle_keypress(KeyCode::Char('u'));
+        assert_eq!(editor.rows.len(), 2);
+        assert_eq!(editor.rows[0][0], "Le chien");
+        editor.redo();
+        assert_eq!(editor.rows.len(), 1);
+
+        // Closing with unsaved changes asks first
+        editor.handle_keypress(KeyCode::Esc);
+        assert!(editor.is_open);
+        editor.handle_keypress(KeyCode::Esc);
+        assert!(!editor.is_open);
+    }
+
+    #[test]
+    fn test_save_keeps_style_and_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path().join("deck.csv");
+        fs::write(&deck, "\"French\",\"English\"\n\"La maison\",\"The house\"\n").unwrap();
+        let deck = deck.to_str().unwrap().to_string();
+        let mut store = ProgressStore::load(dir.path());
+        store.record(&deck, "La maison", true, 10);
+        store.save().unwrap();
+
+        let mut editor = DeckEditor::open(&deck, Some(dir.path().to_path_buf())).unwrap();
+        editor.handle_keypress(KeyCode::Enter);
+        editor.handle_keypress(KeyCode::Char('!'));
+        editor.handle_keypress(KeyCode::Enter);
+        editor.handle_keypress(KeyCode::Char('s'));
+
+        assert!(!editor.dirty);
+        assert_eq!(fs::read_to_string(&deck).unwrap(), "\"French\",\"English\"\n\"La maison!\",\"The house\"\n");
+        let store = ProgressStore::load(dir.path());
+        assert!(store.get(&deck, "La maison").is_none());
+        assert!(store.get(&deck, "La maison!").is_some());
+    }
+}
@@ -1,17 +1,118 @@
-use tui::{Frame, backend::CrosstermBackend};
-use crossterm::event::{KeyCode};
+use tui::{Frame, backend::Backend};
+use crossterm::event::{KeyCode, MouseEvent, MouseEventKind, MouseButton};
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::fmt;
 use std::fs::{self};
-use tui::widgets::{List, ListItem, Paragraph};
-use tui::layout::{Rect, Alignment};
+use std::time::SystemTime;
+use tui::widgets::{List, ListItem, ListState, Paragraph, Wrap};
+use tui::layout::{Rect, Alignment, Layout, Constraint, Direction};
+use tui::style::{Style, Color};
 use std::path::{Path, PathBuf};
+use trashcards::file_handler;
+use trashcards::progress::{self, ProgressStore};
+use trashcards::recent::{self, PathList};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    Name,
+    Modified,
+    Size,
+}
+
+impl SortMode {
+    // Cycle to the next sort mode
+    fn next(self) -> Self {
+        match self {
+            SortMode::Name => SortMode::Modified,
+            SortMode::Modified => SortMode::Size,
+            SortMode::Size => SortMode::Name,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortMode::Name => "name",
+            SortMode::Modified => "modified",
+            SortMode::Size => "size",
+        }
+    }
+}
+
+// A single directory entry with the metadata needed for sorting
+struct FileEntry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+// Summary of a deck shown next to the file list before it is picked
+pub struct DeckPreview {
+    pub headers: Vec<String>,
+    pub row_count: usize,
+    pub samples: Vec<Vec<String>>, // First few rows of the deck
+    pub due: usize,                // Cards studied before that are due today
+    pub new: usize,                // Cards never studied
+}
+
+impl DeckPreview {
+    const SAMPLE_ROWS: usize = 2;
+
+    // Progress is read from the data dir, without one every card counts as new
+    pub fn load(path: &Path, data_dir: Option<&Path>) -> Result<Self, Box<dyn Error>> {
+        let (headers, rows) = file_handler::read_csv_with_headers(path.to_str().unwrap_or_default())?;
+        let store = data_dir.map(ProgressStore::load).unwrap_or_default();
+        let deck = progress::deck_key(path);
+        let questions = || rows.iter().filter_map(|row| row.first()).map(|q| q.as_str());
+
+        Ok(DeckPreview {
+            row_count: rows.len(),
+            samples: rows.iter().take(Self::SAMPLE_ROWS).cloned().collect(),
+            due: store.due_count(&deck, questions(), progress::today()),
+            new: questions().filter(|q| store.get(&deck, q).is_none()).count(),
+            headers,
+        })
+    }
+}
+
+impl fmt::Display for DeckPreview {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Columns: {}", self.headers.join(", "))?;
+        writeln!(f, "Cards: {}", self.row_count)?;
+        writeln!(f, "Due: {}, New: {}", self.due, self.new)?;
+        writeln!(f)?;
+        writeln!(f, "Sample:")?;
+        for row in &self.samples {
+            writeln!(f, "{}", row.join(" | "))?;
+        }
+        Ok(())
+    }
+}
 
 pub struct FileBrowser {
     pub is_open: bool,
     pub current_directory: String,
     pub selected_index: usize, // Track the currently selected file in the list
-    file_list: Vec<String>,  // Store file list as a vector of Strings, which can include ".."
+    file_list: Vec<String>,  // Visible entries after hiding, filtering and sorting, which can include ".."
+    entries: Vec<FileEntry>, // Everything read from the current directory
     pub selected_file: Option<String>, // Store the selected file here
     pub prompt_user: bool, // Flag to display the prompt to start playing or continue browsing
+    pub filter: String, // Fuzzy search pattern typed by the user
+    pub filtering: bool, // True while the filter input has focus, so typed keys go into the filter
+    pub sort_mode: SortMode,
+    pub dirs_first: bool,
+    pub show_hidden: bool,
+    pub decks_only: bool, // Hide files that can't be loaded as a deck instead of dimming them
+    scroll_offset: Cell<usize>, // First visible entry, updated on every draw
+    page_rows: Cell<usize>, // Visible entries in the last drawn window
+    preview_cache: RefCell<Option<(PathBuf, String)>>, // Last previewed deck and its preview text
+    pub recent_decks: PathList, // Most recently played decks
+    pub bookmarks: PathList,    // Bookmarked directories
+    pub bookmarks_open: bool,   // True while the bookmark list is shown over the file list
+    bookmark_index: usize,
+    pub message: Option<String>, // Feedback for the last action, shown above the preview until the next key
+    data_dir: Option<PathBuf>,   // Where the progress shown in the preview lives, set with the history
 }
 
 impl FileBrowser {
@@ -27,11 +128,62 @@ impl FileBrowser {
             current_directory,
             selected_index: 0, // Default to the first file in the list
             file_list: Vec::new(),
+            entries: Vec::new(),
             selected_file: None, // Initially, no file is selected
             prompt_user: false, // Initially no prompt
+            filter: String::new(),
+            filtering: false,
+            sort_mode: SortMode::Name,
+            dirs_first: true,
+            show_hidden: false,
+            decks_only: false,
+            scroll_offset: Cell::new(0),
+            page_rows: Cell::new(10),
+            preview_cache: RefCell::new(None),
+            recent_decks: PathList::default(),
+            bookmarks: PathList::default(),
+            bookmarks_open: false,
+            bookmark_index: 0,
+            message: None,
+            data_dir: None,
         }
     }
 
+    // Load recent decks and bookmarks, and start browsing where the last deck lives. The preview reads progress from the same data dir.
+    pub fn load_history(&mut self, data_dir: &Path) {
+        self.recent_decks = recent::recent_decks(data_dir);
+        self.bookmarks = recent::bookmarks(data_dir);
+        self.data_dir = Some(data_dir.to_path_buf());
+
+        if let Some(parent) = self.recent_decks.entries.first().and_then(|deck| Path::new(deck).parent()) {
+            if parent.is_dir() {
+                self.current_directory = parent.to_str().unwrap_or("/").to_string();
+            }
+        }
+    }
+
+    // Full path of the selected deck
+    pub fn selected_path(&self) -> Option<PathBuf> {
+        self.selected_file.as_ref().map(|file| Path::new(&self.current_directory).join(file))
+    }
+
+    // Select a deck by its full path, e.g. one from the recent list
+    pub fn select_path(&mut self, path: &Path) {
+        if let (Some(parent), Some(name)) = (path.parent(), path.file_name()) {
+            self.current_directory = parent.to_str().unwrap_or("/").to_string();
+            self.selected_file = Some(name.to_string_lossy().to_string());
+        }
+    }
+
+    // Put the selected deck on top of the recent list
+    pub fn remember_selected_deck(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(path) = self.selected_path() {
+            self.recent_decks.push_front(&progress::deck_key(&path));
+            self.recent_decks.save()?;
+        }
+        Ok(())
+    }
+
     // Toggle function to switch between file browser states
     pub fn toggle(&mut self) {
         self.is_open = !self.is_open;
@@ -42,49 +194,180 @@ impl FileBrowser {
 
     // Function to load the file list from the current directory
     fn load_file_list(&mut self) {
-        let mut entries = fs::read_dir(&self.current_directory)
+        self.entries = fs::read_dir(&self.current_directory)
             .unwrap_or_else(|_| fs::read_dir("/").unwrap()) // Handle error by falling back to root directory
             .filter_map(|entry| entry.ok()) // Filter out errors
-            .map(|entry| entry.file_name().into_string().unwrap_or_else(|_| String::from("Unknown")))
-            .collect::<Vec<String>>();
+            .map(|entry| {
+                // Follow symlinks so linked directories still show up as directories
+                let metadata = fs::metadata(entry.path()).ok();
+                FileEntry {
+                    name: entry.file_name().into_string().unwrap_or_else(|_| String::from("Unknown")),
+                    is_dir: metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false),
+                    size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+                    modified: metadata.and_then(|m| m.modified().ok()),
+                }
+            })
+            .collect();
+
+        self.filter.clear();
+        self.filtering = false;
+        self.scroll_offset.set(0);
+        self.preview_cache.replace(None); // Progress may have changed since the last preview
+        self.refresh_view();
+    }
+
+    // Rebuild the visible list from the loaded entries using the current filter and sort settings
+    fn refresh_view(&mut self) {
+        let mut visible: Vec<(i64, &FileEntry)> = self.entries.iter()
+            .filter(|entry| self.show_hidden || !entry.name.starts_with('.'))
+            .filter(|entry| !self.decks_only || entry.is_dir || file_handler::is_supported_deck(Path::new(&entry.name)))
+            .filter_map(|entry| fuzzy_score(&self.filter, &entry.name).map(|score| (score, entry)))
+            .collect();
+
+        visible.sort_by(|(score_a, a), (score_b, b)| {
+            let by_kind = if self.dirs_first { b.is_dir.cmp(&a.is_dir) } else { std::cmp::Ordering::Equal };
+            let by_mode = match self.sort_mode {
+                SortMode::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                SortMode::Modified => b.modified.cmp(&a.modified), // Newest first
+                SortMode::Size => b.size.cmp(&a.size), // Largest first
+            };
+            // While searching, the best matches go to the top
+            by_kind.then(score_b.cmp(score_a)).then(by_mode)
+        });
+
+        let mut file_list: Vec<String> = visible.into_iter().map(|(_, entry)| entry.name.clone()).collect();
 
         // Add ".." to navigate to the parent directory if we are not at the root
         if self.current_directory != "/" {
-            let _parent_dir = Path::new(&self.current_directory).parent().unwrap_or_else(|| Path::new("/"));
-            entries.insert(0, String::from("..")); // Add ".." as a string
+            file_list.insert(0, String::from("..")); // Add ".." as a string
         }
 
-        self.file_list = entries;
+        self.file_list = file_list;
+        if self.selected_index >= self.file_list.len() {
+            self.selected_index = 0;
+        }
+    }
+
+    // Split the area into the list, the filter input (only while a filter is set) and the deck preview
+    fn areas(&self, size: Rect) -> (Rect, Option<Rect>, Rect) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+            .split(size);
+
+        if self.filtering || !self.filter.is_empty() {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+                .split(columns[0]);
+            (rows[0], Some(rows[1]), columns[1])
+        } else {
+            (columns[0], None, columns[1])
+        }
+    }
+
+    fn list_area(&self, size: Rect) -> Rect {
+        self.areas(size).0
+    }
+
+    // Number of entries that fit inside the bordered list
+    fn visible_rows(&self, size: Rect) -> usize {
+        self.list_area(size).height.saturating_sub(2).max(1) as usize
+    }
+
+    // Keep the selected entry inside the visible window and return the first visible index
+    fn update_scroll(&self, rows: usize) -> usize {
+        let mut offset = self.scroll_offset.get();
+        if self.selected_index < offset {
+            offset = self.selected_index;
+        } else if self.selected_index >= offset + rows {
+            offset = self.selected_index + 1 - rows;
+        }
+        self.scroll_offset.set(offset);
+        offset
     }
 
     // Function to draw the file browser as a popup in the main window
-    pub fn draw_popup(&self, f: &mut Frame<CrosstermBackend<std::io::Stdout>>, size: Rect) {
+    pub fn draw_popup<B: Backend>(&self, f: &mut Frame<B>, size: Rect) {
         if self.is_open {
-            // Ensure the selected index is within bounds
-            let items: Vec<ListItem> = self.file_list.iter().enumerate().map(|(i, file)| {
+            let (list_area, filter_area, preview_area) = self.areas(size);
+            let rows = self.visible_rows(size);
+            let offset = self.update_scroll(rows);
+            self.page_rows.set(rows);
+
+            // Only build items for the visible window, large directories stay cheap to draw
+            let items: Vec<ListItem> = self.file_list.iter().skip(offset).take(rows).map(|file| {
                 // Check if the item is a directory and append '/' to the name
+                let full_path = Path::new(&self.current_directory).join(file);
                 let display_name = if file == ".." {
                     String::from("..")  // Special case for ".."
+                } else if full_path.is_dir() {
+                    format!("{}/", file)  // Add '/' for directories
                 } else {
-                    let full_path = Path::new(&self.current_directory).join(file);
-                    if full_path.is_dir() {
-                        format!("{}/", file)  // Add '/' for directories
-                    } else {
-                        file.clone() // Just the file name for regular files
-                    }
+                    file.clone() // Just the file name for regular files
                 };
 
-                let mut item = ListItem::new(display_name);
-                if i == self.selected_index {
-                    item = item.style(tui::style::Style::default().fg(tui::style::Color::Yellow)); // Highlight the selected file
+                // Dim files that can't be loaded as a deck
+                if file != ".." && !full_path.is_dir() && !file_handler::is_supported_deck(&full_path) {
+                    ListItem::new(display_name).style(Style::default().fg(Color::DarkGray))
+                } else {
+                    ListItem::new(display_name)
                 }
-                item
             }).collect();
 
+            let title = format!(
+                "File Browser{} ({}/{}) sort: {}{}{}{}",
+                if self.bookmarks.contains(&self.current_directory) { " [bookmarked]" } else { "" },
+                if self.file_list.is_empty() { 0 } else { self.selected_index + 1 },
+                self.file_list.len(),
+                self.sort_mode.label(),
+                if self.dirs_first { ", dirs first" } else { "" },
+                if self.show_hidden { ", hidden shown" } else { "" },
+                if self.decks_only { ", decks only" } else { "" },
+            );
+
             let list = List::new(items)
-                .block(tui::widgets::Block::default().title("File Browser").borders(tui::widgets::Borders::ALL));
+                .block(tui::widgets::Block::default().title(title).borders(tui::widgets::Borders::ALL))
+                .highlight_style(Style::default().fg(Color::Yellow)); // Highlight the selected file
+
+            let mut state = ListState::default();
+            if !self.file_list.is_empty() {
+                state.select(Some(self.selected_index - offset));
+            }
+            f.render_stateful_widget(list, list_area, &mut state);
 
-            f.render_widget(list, size);
+            if let Some(filter_area) = filter_area {
+                let cursor = if self.filtering { "_" } else { "" };
+                let filter_input = Paragraph::new(format!("{}{}", self.filter, cursor))
+                    .block(tui::widgets::Block::default().title("Filter (Enter: keep, Esc: clear)").borders(tui::widgets::Borders::ALL));
+                f.render_widget(filter_input, filter_area);
+            }
+
+            let preview_text = match self.message {
+                Some(ref message) => format!("{}\n\n{}", message, self.preview_text()),
+                None => self.preview_text(),
+            };
+            let preview = Paragraph::new(preview_text)
+                .block(tui::widgets::Block::default().title("Preview").borders(tui::widgets::Borders::ALL))
+                .wrap(Wrap { trim: false });
+            f.render_widget(preview, preview_area);
+
+            if self.bookmarks_open {
+                let items: Vec<ListItem> = if self.bookmarks.entries.is_empty() {
+                    vec![ListItem::new("No bookmarks yet, press 'b' in a directory to add one")]
+                } else {
+                    self.bookmarks.entries.iter().map(|dir| ListItem::new(dir.as_str())).collect()
+                };
+                let list = List::new(items)
+                    .block(tui::widgets::Block::default().title("Bookmarks (Enter: open, x: remove, Esc: close)").borders(tui::widgets::Borders::ALL))
+                    .highlight_style(Style::default().fg(Color::Yellow));
+                let mut state = ListState::default();
+                if !self.bookmarks.entries.is_empty() {
+                    state.select(Some(self.bookmark_index));
+                }
+                f.render_widget(tui::widgets::Clear, list_area);
+                f.render_stateful_widget(list, list_area, &mut state);
+            }
 
             // If a file is selected, prompt the user
             if self.prompt_user {
@@ -97,27 +380,76 @@ impl FileBrowser {
         }
     }
 
+    // Preview of the highlighted entry, the deck is only read again when the highlight moves
+    fn preview_text(&self) -> String {
+        let Some(file) = self.get_selected_file() else {
+            return String::new();
+        };
+        let full_path = Path::new(&self.current_directory).join(file);
+        if file == ".." || full_path.is_dir() {
+            return String::from("Directory");
+        }
+        if !file_handler::is_supported_deck(&full_path) {
+            return format!("Not a supported deck ({})", file_handler::SUPPORTED_EXTENSIONS.join(", "));
+        }
+
+        let mut cache = self.preview_cache.borrow_mut();
+        if cache.as_ref().map(|(path, _)| path != &full_path).unwrap_or(true) {
+            let text = match DeckPreview::load(&full_path, self.data_dir.as_deref()) {
+                Ok(preview) => preview.to_string(),
+                Err(err) => format!("Could not read deck: {}", err),
+            };
+            *cache = Some((full_path, text));
+        }
+        cache.as_ref().map(|(_, text)| text.clone()).unwrap_or_default()
+    }
+
     // Function to handle keypresses for file navigation (navigate directories and select files)
     pub fn handle_keypress(&mut self, key: KeyCode) {
+        self.message = None;
+        if self.filtering {
+            self.handle_filter_keypress(key);
+            return;
+        }
+        if self.bookmarks_open {
+            self.handle_bookmarks_keypress(key);
+            return;
+        }
+
         match key {
+            // Handle any other keypress when prompt is shown (continue browsing)
+            KeyCode::Esc | KeyCode::Char(_) if self.prompt_user => {
+                self.prompt_user = false;  // Hide the prompt and let the user continue browsing
+            }
             // Navigate through the file list with arrow keys
-            KeyCode::Down => {
+            KeyCode::Down if self.get_file_count() > 0 => {
                 self.selected_index = (self.selected_index + 1) % self.get_file_count();
             }
             KeyCode::Up => {
                 if self.selected_index == 0 {
-                    self.selected_index = self.get_file_count() - 1;
+                    self.selected_index = self.get_file_count().saturating_sub(1);
                 } else {
                     self.selected_index -= 1;
                 }
             }
+            // Jump a page at a time, using the size of the last drawn window
+            KeyCode::PageDown => {
+                let rows = self.page_size();
+                self.selected_index = (self.selected_index + rows).min(self.get_file_count().saturating_sub(1));
+            }
+            KeyCode::PageUp => {
+                let rows = self.page_size();
+                self.selected_index = self.selected_index.saturating_sub(rows);
+            }
+            KeyCode::Home => self.selected_index = 0,
+            KeyCode::End => self.selected_index = self.get_file_count().saturating_sub(1),
             // Enter key to "select" or enter a directory
             KeyCode::Enter => {
                 // First, get the selected file
                 if let Some(selected_file) = self.get_selected_file() {
                     // Temporarily hold the selected file in a variable
                     let file_to_select = selected_file.clone();
-    
+
                     // If ".." is selected, navigate to the parent directory
                     if file_to_select == ".." {
                         self.navigate_up();
@@ -128,23 +460,167 @@ impl FileBrowser {
                             self.current_directory = full_path.to_str().unwrap_or("/").to_string();
                             self.selected_index = 0; // Reset selection when entering a new directory
                             self.load_file_list(); // Reload the file list when entering a new directory
+                        } else if !file_handler::is_supported_deck(&full_path) {
+                            // Not a deck, nothing to play with it
                         } else {
                             // Now assign the selected file to `selected_file` after other logic
                             self.selected_file = Some(file_to_select);
-            
+
                             // Set prompt_user to true to show the prompt to start playing
                             self.prompt_user = true;
                             // We do not close the file browser yet, so the user can choose
                         }
                     }
-    
+
                 }
-                
+
             }
-            // Handle any other keypress when prompt is shown (continue browsing)
-            KeyCode::Esc | KeyCode::Char(_) => {
+            // '/' starts typing a fuzzy filter
+            KeyCode::Char('/') => {
+                self.filtering = true;
+            }
+            // Clear a kept filter
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.refresh_view();
+            }
+            // 'o' cycles the sort order: name, modification time, size
+            KeyCode::Char('o') => {
+                self.sort_mode = self.sort_mode.next();
+                self.refresh_view();
+            }
+            // 'd' toggles listing directories before files
+            KeyCode::Char('d') => {
+                self.dirs_first = !self.dirs_first;
+                self.refresh_view();
+            }
+            // '.' toggles hidden files
+            KeyCode::Char('.') => {
+                self.show_hidden = !self.show_hidden;
+                self.refresh_view();
+            }
+            // 'b' bookmarks the current directory, or removes its bookmark
+            KeyCode::Char('b') => {
+                if self.bookmarks.contains(&self.current_directory) {
+                    self.bookmarks.remove(&self.current_directory);
+                } else {
+                    self.bookmarks.push_front(&self.current_directory);
+                }
+                self.save_bookmarks();
+            }
+            // 'g' opens the bookmark list
+            KeyCode::Char('g') => {
+                self.bookmarks_open = true;
+                self.bookmark_index = 0;
+            }
+            // 'f' toggles hiding everything that isn't a deck or a directory
+            KeyCode::Char('f') => {
+                self.decks_only = !self.decks_only;
+                self.refresh_view();
+            }
+            _ => {}
+        }
+    }
+
+    // Keys typed while the filter input has focus
+    fn handle_filter_keypress(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char(c) => self.filter.push(c),
+            KeyCode::Backspace => {
+                self.filter.pop();
+            }
+            // Enter keeps the filter and goes back to browsing the matches
+            KeyCode::Enter => self.filtering = false,
+            // Esc drops the filter altogether
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.filtering = false;
+            }
+            // Arrows still move the selection while typing
+            KeyCode::Up | KeyCode::Down => {
+                self.filtering = false;
+                self.handle_keypress(key);
+                self.filtering = true;
+                return;
+            }
+            _ => return,
+        }
+        self.refresh_view();
+        // Start from the best match (just below "..") every time the pattern changes
+        let has_parent = self.file_list.first().map(|f| f == "..").unwrap_or(false);
+        self.selected_index = if !self.filter.is_empty() && has_parent && self.file_list.len() > 1 { 1 } else { 0 };
+        self.scroll_offset.set(0);
+    }
+
+    // Keys handled while the bookmark list is open
+    fn handle_bookmarks_keypress(&mut self, key: KeyCode) {
+        let count = self.bookmarks.entries.len();
+        match key {
+            KeyCode::Down if count > 0 => self.bookmark_index = (self.bookmark_index + 1) % count,
+            KeyCode::Up if count > 0 => self.bookmark_index = (self.bookmark_index + count - 1) % count,
+            // Jump to the highlighted directory
+            KeyCode::Enter => {
+                if let Some(dir) = self.bookmarks.entries.get(self.bookmark_index) {
+                    if Path::new(dir).is_dir() {
+                        self.current_directory = dir.clone();
+                        self.selected_index = 0;
+                        self.load_file_list();
+                    }
+                }
+                self.bookmarks_open = false;
+            }
+            // Drop the highlighted bookmark
+            KeyCode::Delete | KeyCode::Char('x') => {
+                if let Some(dir) = self.bookmarks.entries.get(self.bookmark_index).cloned() {
+                    self.bookmarks.remove(&dir);
+                    self.bookmark_index = self.bookmark_index.min(self.bookmarks.entries.len().saturating_sub(1));
+                    self.save_bookmarks();
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('g') => self.bookmarks_open = false,
+            _ => {}
+        }
+    }
+
+    fn save_bookmarks(&mut self) {
+        if let Err(err) = self.bookmarks.save() {
+            self.message = Some(format!("Error saving bookmarks: {}", err));
+        }
+    }
+
+    // Number of entries moved by PageUp/PageDown, taken from the last drawn window
+    fn page_size(&self) -> usize {
+        self.page_rows.get().max(1)
+    }
+
+    // Function to handle mouse input: click selects an entry, clicking it again opens it, wheel scrolls
+    pub fn handle_mouse(&mut self, event: MouseEvent, size: Rect) {
+        if self.file_list.is_empty() || self.bookmarks_open {
+            return;
+        }
+        match event.kind {
+            MouseEventKind::ScrollDown => self.handle_keypress(KeyCode::Down),
+            MouseEventKind::ScrollUp => self.handle_keypress(KeyCode::Up),
+            MouseEventKind::Down(MouseButton::Left) => {
                 if self.prompt_user {
-                    self.prompt_user = false;  // Hide the prompt and let the user continue browsing
+                    // Clicking anywhere dismisses the prompt, like any other key
+                    self.prompt_user = false;
+                    return;
+                }
+                // Entries start one row below the top border of the list block
+                let list_area = self.list_area(size);
+                if event.row <= list_area.y || event.row >= list_area.y + list_area.height.saturating_sub(1)
+                    || event.column <= list_area.x || event.column >= list_area.x + list_area.width.saturating_sub(1) {
+                    return;
+                }
+                let index = self.scroll_offset.get() + (event.row - list_area.y - 1) as usize;
+                if index >= self.get_file_count() {
+                    return;
+                }
+                if index == self.selected_index {
+                    self.handle_keypress(KeyCode::Enter);
+                } else {
+                    self.selected_index = index;
                 }
             }
             _ => {}
@@ -174,13 +650,45 @@ impl FileBrowser {
         }
     }
 }
+
+// Case-insensitive fuzzy match: every pattern character has to appear in order.
+// Returns None when the candidate doesn't match, otherwise a score where higher is better.
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i64> {
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut last_match: Option<usize> = None;
+    let mut position = 0;
+
+    for p in pattern.to_lowercase().chars() {
+        let found = candidate[position..].iter().position(|&c| c == p)? + position;
+
+        score += 1;
+        // Reward runs of consecutive characters
+        if last_match.map(|last| last + 1 == found).unwrap_or(false) {
+            score += 5;
+        }
+        // Reward matches at the start of a word
+        if found == 0 || matches!(candidate[found - 1], ' ' | '-' | '_' | '.') {
+            score += 8;
+        }
+        // Penalize gaps between matched characters
+        score -= (found - last_match.map(|last| last + 1).unwrap_or(0)) as i64;
+
+        last_match = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crossterm::event::{KeyCode};
+    use crossterm::event::{KeyCode, KeyModifiers};
     use std::env;
-    use std::fs::{self};
-    use std::path::Path;
 
     // Test case for initializing a new FileBrowser instance
     #[test]
@@ -238,7 +746,7 @@ mod tests {
 
         // Now, simulate a file that exists within the loaded directory
         // For testing purposes, let's simulate that "test_file.txt" exists in the current directory
-        let test_file = "huhcat.gif";  // Set the name of a test file here
+        let test_file = "test-fr-en.csv";  // Set the name of a test file here, it has to be a supported deck
         
         // Make sure the file is in the file list for testing (add manually if needed)
         if !file_browser.file_list.contains(&test_file.to_string()) {
@@ -302,4 +810,235 @@ mod tests {
         file_browser.handle_keypress(KeyCode::Char('x'));
         assert_eq!(file_browser.prompt_user, false, "Prompt should disappear after pressing a different key");
     }
+
+    // Test case for selecting and opening entries with the mouse
+    #[test]
+    fn test_handle_mouse_click_and_scroll() {
+        let mut file_browser = FileBrowser::new();
+        file_browser.file_list = vec![String::from(".."), String::from("a.csv"), String::from("b.csv")];
+        let size = Rect::new(10, 5, 40, 10);
+        let click = |row| MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 12,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+
+        // First click on the third row selects "b.csv"
+        file_browser.handle_mouse(click(8), size);
+        assert_eq!(file_browser.selected_index, 2);
+        assert_eq!(file_browser.selected_file, None);
+
+        // Scrolling up moves the selection
+        file_browser.handle_mouse(MouseEvent { kind: MouseEventKind::ScrollUp, column: 12, row: 8, modifiers: KeyModifiers::NONE }, size);
+        assert_eq!(file_browser.selected_index, 1);
+
+        // Clicking the selected entry opens it
+        file_browser.handle_mouse(click(7), size);
+        assert_eq!(file_browser.selected_file, Some(String::from("a.csv")));
+        assert!(file_browser.prompt_user);
+    }
+
+    // Helper to create a browser on a temporary directory with a few files in it
+    fn browser_with_files(files: &[(&str, usize)]) -> (tempfile::TempDir, FileBrowser) {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, size) in files {
+            fs::write(dir.path().join(name), "x".repeat(*size)).unwrap();
+        }
+        fs::create_dir(dir.path().join("decks")).unwrap();
+
+        let mut file_browser = FileBrowser::new();
+        file_browser.current_directory = dir.path().to_str().unwrap().to_string();
+        file_browser.load_file_list();
+        (dir, file_browser)
+    }
+
+    // Test case for fuzzy scoring
+    #[test]
+    fn test_fuzzy_score() {
+        // Empty pattern matches everything
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+
+        // Characters have to appear in order
+        assert!(fuzzy_score("frn", "test-fr-en.csv").is_some());
+        assert!(fuzzy_score("nrf", "test-fr-en.csv").is_none());
+
+        // Consecutive and word-start matches score higher than scattered ones
+        let tight = fuzzy_score("fr", "test-fr-en.csv").unwrap();
+        let loose = fuzzy_score("fr", "foo-bar.csv").unwrap();
+        assert!(tight > loose, "Consecutive match should beat a scattered one");
+    }
+
+    // Test case for hiding and showing dot files and listing directories first
+    #[test]
+    fn test_hidden_files_and_dirs_first() {
+        let (_dir, mut file_browser) = browser_with_files(&[("b.csv", 1), ("a.csv", 1), (".secret", 1)]);
+
+        // Hidden files are not listed by default, directories come first
+        assert_eq!(file_browser.file_list, vec!["..", "decks", "a.csv", "b.csv"]);
+
+        // Toggle hidden files
+        file_browser.handle_keypress(KeyCode::Char('.'));
+        assert!(file_browser.file_list.contains(&String::from(".secret")));
+
+        // Toggle directories first off, so everything is sorted by name
+        file_browser.handle_keypress(KeyCode::Char('.'));
+        file_browser.handle_keypress(KeyCode::Char('d'));
+        assert_eq!(file_browser.file_list, vec!["..", "a.csv", "b.csv", "decks"]);
+    }
+
+    // Test case for sorting by size
+    #[test]
+    fn test_sort_by_size() {
+        let (_dir, mut file_browser) = browser_with_files(&[("small.csv", 1), ("big.csv", 100), ("medium.csv", 10)]);
+
+        // Cycle name -> modified -> size
+        file_browser.handle_keypress(KeyCode::Char('o'));
+        file_browser.handle_keypress(KeyCode::Char('o'));
+        assert_eq!(file_browser.sort_mode, SortMode::Size);
+        assert_eq!(file_browser.file_list, vec!["..", "decks", "big.csv", "medium.csv", "small.csv"]);
+    }
+
+    // Test case for typing a filter
+    #[test]
+    fn test_type_to_filter() {
+        let (_dir, mut file_browser) = browser_with_files(&[("test-fr-en.csv", 1), ("questions.csv", 1)]);
+
+        // Start filtering and type a pattern
+        file_browser.handle_keypress(KeyCode::Char('/'));
+        assert!(file_browser.filtering);
+        for c in "fren".chars() {
+            file_browser.handle_keypress(KeyCode::Char(c));
+        }
+
+        // Only the matching file is left, ".." stays on top and the match is selected
+        assert_eq!(file_browser.file_list, vec!["..", "test-fr-en.csv"]);
+        assert_eq!(file_browser.get_selected_file(), Some(&String::from("test-fr-en.csv")));
+
+        // Backspace widens the filter again
+        file_browser.handle_keypress(KeyCode::Backspace);
+        assert_eq!(file_browser.filter, "fre");
+
+        // Enter keeps the filter, Esc afterwards clears it
+        file_browser.handle_keypress(KeyCode::Enter);
+        assert!(!file_browser.filtering);
+        file_browser.handle_keypress(KeyCode::Esc);
+        assert!(file_browser.filter.is_empty());
+        assert_eq!(file_browser.file_list.len(), 4);
+    }
+
+    // Test case for keeping the selection visible while scrolling
+    #[test]
+    fn test_scroll_follows_selection() {
+        let mut file_browser = FileBrowser::new();
+        file_browser.file_list = (0..50).map(|i| format!("file{}.csv", i)).collect();
+
+        // Move the selection below a window of 10 rows
+        file_browser.selected_index = 25;
+        assert_eq!(file_browser.update_scroll(10), 16);
+
+        // Moving back up scrolls the window up with it
+        file_browser.selected_index = 3;
+        assert_eq!(file_browser.update_scroll(10), 3);
+
+        // PageDown jumps a page of the last drawn size
+        file_browser.page_rows.set(10);
+        file_browser.handle_keypress(KeyCode::PageDown);
+        assert_eq!(file_browser.selected_index, 13);
+        file_browser.handle_keypress(KeyCode::End);
+        assert_eq!(file_browser.selected_index, 49);
+    }
+
+    // Test case for refusing files that are not decks
+    #[test]
+    fn test_select_non_deck_file() {
+        let (_dir, mut file_browser) = browser_with_files(&[("huhcat.gif", 1), ("deck.csv", 1)]);
+
+        // Select the gif and press Enter
+        file_browser.selected_index = file_browser.file_list.iter().position(|x| x == "huhcat.gif").unwrap();
+        file_browser.handle_keypress(KeyCode::Enter);
+        assert_eq!(file_browser.selected_file, None, "A gif should not be selectable as a deck");
+        assert!(!file_browser.prompt_user);
+
+        // Hide everything that is not a deck
+        file_browser.handle_keypress(KeyCode::Char('f'));
+        assert_eq!(file_browser.file_list, vec!["..", "decks", "deck.csv"]);
+    }
+
+    // Test case for the deck preview
+    #[test]
+    fn test_deck_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fr.csv");
+        fs::write(&path, "French;English\nLa maison;The house\nLe chien;The dog\nLa voiture;The car\n").unwrap();
+
+        let preview = DeckPreview::load(&path, Some(&dir.path().join("data"))).unwrap();
+        assert_eq!(preview.headers, vec!["French", "English"]);
+        assert_eq!(preview.row_count, 3);
+        assert_eq!(preview.samples.len(), DeckPreview::SAMPLE_ROWS);
+        assert_eq!(preview.new, 3, "A fresh deck has no progress");
+        assert_eq!(preview.due, 0);
+
+        let text = preview.to_string();
+        assert!(text.contains("Columns: French, English"));
+        assert!(text.contains("La maison | The house"));
+    }
+
+    // Test case for bookmarking a directory and jumping back to it
+    #[test]
+    fn test_bookmarks() {
+        let (dir, mut file_browser) = browser_with_files(&[("deck.csv", 1)]);
+        let start = file_browser.current_directory.clone();
+
+        // Bookmark the directory
+        file_browser.handle_keypress(KeyCode::Char('b'));
+        assert!(file_browser.bookmarks.contains(&start));
+
+        // Walk into a subdirectory
+        file_browser.current_directory = dir.path().join("decks").to_str().unwrap().to_string();
+        file_browser.load_file_list();
+
+        // Open the bookmark list and jump back
+        file_browser.handle_keypress(KeyCode::Char('g'));
+        assert!(file_browser.bookmarks_open);
+        file_browser.handle_keypress(KeyCode::Enter);
+        assert!(!file_browser.bookmarks_open);
+        assert_eq!(file_browser.current_directory, start);
+
+        // Remove it again from the list
+        file_browser.handle_keypress(KeyCode::Char('g'));
+        file_browser.handle_keypress(KeyCode::Char('x'));
+        assert!(file_browser.bookmarks.entries.is_empty());
+    }
+
+    // Test case for a bookmark that can't be saved, the error shows above the preview
+    #[test]
+    fn test_bookmark_save_error() {
+        let (dir, mut file_browser) = browser_with_files(&[("deck.csv", 1)]);
+        // The bookmark file would live inside a regular file
+        file_browser.bookmarks = PathList::load(dir.path().join("deck.csv").join("bookmarks"), None);
+
+        file_browser.handle_keypress(KeyCode::Char('b'));
+        assert!(file_browser.message.as_ref().unwrap().starts_with("Error saving bookmarks"));
+
+        // Gone with the next key
+        file_browser.handle_keypress(KeyCode::Down);
+        assert_eq!(file_browser.message, None);
+    }
+
+    // Test case for starting in the directory of the last deck
+    #[test]
+    fn test_load_history() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let decks_dir = tempfile::tempdir().unwrap();
+        let deck = decks_dir.path().join("fr.csv");
+        fs::write(&deck, "French;English\n").unwrap();
+        fs::write(data_dir.path().join("recent.txt"), format!("{}\n", deck.to_str().unwrap())).unwrap();
+
+        let mut file_browser = FileBrowser::new();
+        file_browser.load_history(data_dir.path());
+        assert_eq!(file_browser.recent_decks.entries, vec![deck.to_str().unwrap()]);
+        assert_eq!(file_browser.current_directory, decks_dir.path().to_str().unwrap());
+    }
 }
+
@@ -1,31 +1,87 @@
-use crossterm::event::{KeyCode};
+use crossterm::event::{KeyCode, MouseEvent, MouseEventKind, MouseButton};
 use tui::{
-    backend::CrosstermBackend,
-    layout::{Alignment, Rect},
-    widgets::{Block, Borders, Paragraph},
+    backend::Backend,
+    layout::{Alignment, Constraint, Rect},
+    style::{Color, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
     Frame,
 };
+use crate::app::editor::TextInput;
 use crate::app::file_browser::FileBrowser;
+use crate::app::rich_text;
+use std::path::{Path, PathBuf};
+use rand::{rngs::StdRng, SeedableRng};
+use trashcards::scheduler::LEECH_LAPSES;
+use trashcards::session::{CardStatus, Session, ShuffleMode, NOT_EDITABLE, RETRY_AFTER};
+use trashcards::file_handler;
+use trashcards::query::Query;
+use trashcards::tags::TagFilter;
 
+// Cards per session unless the command line says otherwise
+pub const DEFAULT_SESSION_SIZE: usize = 5;
+
+// Where the cards of another round come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Round {
+    Deck,               // The deck of the session, or the same cards again without a deck file
+    Decks(Vec<String>), // Several decks studied together
+    Query { decks: Vec<String>, query: Query, size: usize }, // The cards of the decks matching a query
+}
+
+// Inline form for fixing the current card, one input per column and one for the tags if the deck has them
+pub struct CardForm {
+    pub fields: Vec<TextInput>,
+    pub focus: usize,
+    pub error: Option<String>, // Why the last save failed
+}
 
 pub struct Game {
     pub is_open: bool,
-    current_row: usize,
+    pub session: Session,   // Cards being studied and their progress
     current_col: usize,
-    data: Vec<Vec<String>>, // Stores the rows from the CSV
-    headers: Vec<String>,   // Stores the column names
     answer_visible: bool,   // Tracks if the answer is visible
+    pub session_size: usize, // Number of cards drawn from the deck
+    pub columns: Option<Vec<usize>>, // Question column followed by the answer columns, None keeps the file order
+    pub shuffle: ShuffleMode, // Order the deck is put in before the session takes its cards
+    pub filter: Option<TagFilter>, // Only cards whose tags match are drawn
+    pub round: Round,       // What another round studies
+    pub data_dir: Option<PathBuf>, // Where sessions read and write progress and the review log, no dir means no deck can be studied
+    pub rng: StdRng,        // Draws the cards of each session, seeded by --seed, the config or a recording
+    pub retry_after: usize, // Cards between a missed card and its next try
+    pub leech_lapses: u32,  // Lapses that make a card a leech
+    pub suspend_leeches: bool,
+    pub form: Option<CardForm>, // Open while the current card is being edited
+    pub jump: Option<String>,   // Card number typed after 'g'
+    pub list: Option<usize>,    // Card highlighted in the card list overlay, None while it's closed
+    views: Vec<(usize, bool)>,  // Column and answer visibility before each session action, for undo
+    redo_views: Vec<(usize, bool)>,
+    pub message: Option<String>, // Why the last deck couldn't be loaded
 }
 
 impl Game {
     pub fn new() -> Self {
         Game {
             is_open: false,
-            current_row: 0,
+            session: Session::new(vec![], vec![]),
             current_col: 0, // Start at column 0, waiting for space to reveal answers
-            data: vec![],
-            headers: vec![], // Initialize headers
             answer_visible: false, // Start with answer hidden
+            session_size: DEFAULT_SESSION_SIZE,
+            columns: None,
+            shuffle: ShuffleMode::default(),
+            filter: None,
+            round: Round::Deck,
+            data_dir: None,
+            rng: StdRng::from_entropy(),
+            retry_after: RETRY_AFTER,
+            leech_lapses: LEECH_LAPSES,
+            suspend_leeches: false,
+            form: None,
+            jump: None,
+            list: None,
+            views: Vec::new(),
+            redo_views: Vec::new(),
+            message: None,
         }
     }
     
@@ -34,46 +90,341 @@ impl Game {
         if let Some(ref selected_file) = file_browser.selected_file {
             // Get the full file path from the file browser
             let file_path = format!("{}/{}", file_browser.current_directory, selected_file);
-            let num_rows = 5;
-
             // Process the CSV file
-            let (headers, rows) = crate::app::file_handler::read_and_process_csv_with_headers(&file_path, num_rows)?;
-            self.headers = headers;
-            self.data = rows;
+            let session = Session::load(&self.data_dir()?, &file_path, self.session_size, self.columns.as_deref(), self.filter.as_ref(), self.shuffle, &mut self.rng)?;
+            self.start(self.configure(session));
+            self.round = Round::Deck;
             Ok(())
         } else {
             Err("No file selected".into())
         }
     }
 
+    // The data dir is set by main, tests give each game a temp dir of its own
+    fn data_dir(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        self.data_dir.clone().ok_or_else(|| "No data dir to keep the progress in".into())
+    }
+
+    // Study an already loaded session, starting at the question of its first card
+    pub fn start(&mut self, session: Session) {
+        self.session = session;
+        self.form = None;
+        self.jump = None;
+        self.list = None;
+        self.views.clear();
+        self.redo_views.clear();
+        self.current_col = 0;
+        self.answer_visible = false;
+        self.message = None;
+    }
+
+    // Apply the settings from the config to a freshly loaded session, unless its deck has its own
+    fn configure(&self, mut session: Session) -> Session {
+        session.configure(self.retry_after, self.leech_lapses, self.suspend_leeches);
+        session
+    }
+
+    // Study the cards of several decks that match a query, another round runs the query again
+    pub fn start_query(&mut self, decks: Vec<String>, query: Query) {
+        self.run_query(decks, query, self.session_size);
+    }
+
+    // Review every card that is due in the decks, however many there are
+    pub fn start_due(&mut self, decks: Vec<String>) {
+        let query = "due:today".parse().expect("due:today is a valid query");
+        self.run_query(decks, query, usize::MAX);
+    }
+
+    fn run_query(&mut self, decks: Vec<String>, query: Query, size: usize) {
+        let data_dir = match self.data_dir() {
+            Ok(data_dir) => data_dir,
+            Err(err) => {
+                self.start(Session::new(vec![], vec![]));
+                self.message = Some(err.to_string());
+                return;
+            }
+        };
+        let session = Session::query(&data_dir, &decks, &query, size, self.shuffle, &mut self.rng);
+        self.start(self.configure(session));
+        self.round = Round::Query { decks, query, size };
+        self.is_open = true;
+    }
+
+    // Draw cards from several decks at once, each card shows the headers of its own deck
+    pub fn start_decks(&mut self, decks: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+        let session = Session::load_decks(&self.data_dir()?, &decks, self.session_size, self.filter.as_ref(), self.shuffle, &mut self.rng)?;
+        self.start(self.configure(session));
+        self.round = Round::Decks(decks);
+        self.is_open = true;
+        Ok(())
+    }
+
+    // Draw new cards from the same deck, a session without a deck file just starts over
+    fn another_round(&mut self) {
+        match self.round.clone() {
+            Round::Query { decks, query, size } => return self.run_query(decks, query, size),
+            Round::Decks(decks) => {
+                if let Err(err) = self.start_decks(decks) {
+                    self.message = Some(format!("Error loading CSV: {}", err));
+                }
+                return;
+            }
+            Round::Deck => {}
+        }
+        let session = std::mem::replace(&mut self.session, Session::new(vec![], vec![]));
+        if session.deck.is_empty() {
+            let cards = session.cards.clone();
+            self.start(session.with_cards(cards));
+            return;
+        }
+        let next = self.data_dir().and_then(|data_dir| {
+            Session::load(&data_dir, &session.deck, self.session_size, self.columns.as_deref(), self.filter.as_ref(), self.shuffle, &mut self.rng)
+        });
+        match next {
+            Ok(next) => self.start(self.configure(next)),
+            Err(err) => {
+                self.session = session;
+                self.message = Some(format!("Error loading CSV: {}", err));
+            }
+        }
+    }
+
     pub fn toggle(&mut self, file_browser: &FileBrowser) {
         self.is_open = !self.is_open;
         if self.is_open {
-            self.load_csv(file_browser).unwrap_or_else(|err| eprintln!("Error loading CSV: {}", err));
+            if let Err(err) = self.load_csv(file_browser) {
+                self.message = Some(format!("Error loading CSV: {}", err));
+            }
         }
     }
 
+    // True while the edit form, the card number input or the card list has focus, so keys go there
+    pub fn is_typing(&self) -> bool {
+        self.form.is_some() || self.jump.is_some() || self.list.is_some()
+    }
+
     pub fn handle_keypress(&mut self, key: KeyCode) {
-        if key == KeyCode::Char(' ') {
-            self.next();
+        if self.form.is_some() {
+            self.handle_form_keypress(key);
+            return;
+        }
+        if self.jump.is_some() {
+            self.handle_jump_keypress(key);
+            return;
+        }
+        if self.list.is_some() {
+            self.handle_list_keypress(key);
+            return;
+        }
+        // 'u' takes back the last grade, skip or edit, also from the summary
+        if key == KeyCode::Char('u') {
+            self.undo();
+            return;
+        }
+        if self.session.is_finished() {
+            match key {
+                // 'r' goes over the missed cards again, 'a' draws another round from the deck
+                KeyCode::Char('r') if !self.session.missed_cards().is_empty() => {
+                    let session = std::mem::replace(&mut self.session, Session::new(vec![], vec![]));
+                    self.start(session.retry_missed());
+                }
+                KeyCode::Char('a') => self.another_round(),
+                _ => self.navigate(key),
+            }
+            return;
+        }
+
+        // A message about the last key is gone with the next one
+        self.message = None;
+        match key {
+            KeyCode::Char(' ') => self.next(),
+            // Grade the card once all of its answers were shown
+            KeyCode::Char('y') => self.grade(true),
+            KeyCode::Char('n') => self.grade(false),
+            // 'e' fixes a typo in the current card, as long as its deck is a CSV file
+            KeyCode::Char('e') if !self.session.is_editable(self.session.current) => self.message = Some(String::from(NOT_EDITABLE)),
+            KeyCode::Char('e') => {
+                if let Some(card) = self.session.current_card() {
+                    let mut fields: Vec<TextInput> = (0..self.session.card_headers(self.session.current).len()).map(|i| TextInput::new(card.get(i).map(String::as_str).unwrap_or(""))).collect();
+                    if self.session.has_tags(self.session.current) {
+                        fields.push(TextInput::new(&self.session.tags[self.session.current]));
+                    }
+                    self.form = Some(CardForm { fields, focus: 0, error: None });
+                }
+            }
+            _ => self.navigate(key),
+        }
+    }
+
+    // Keys that move between cards, they work on the summary too
+    fn navigate(&mut self, key: KeyCode) {
+        match key {
+            // 'p' or Left goes back to the previous card
+            KeyCode::Char('p') | KeyCode::Left => {
+                let view = (self.current_col, self.answer_visible);
+                if self.session.previous() {
+                    self.show_question(view);
+                }
+            }
+            // 'g' asks for a card number to jump to
+            KeyCode::Char('g') if !self.session.cards.is_empty() => self.jump = Some(String::new()),
+            // 'c' lists every card of the session with how it went
+            KeyCode::Char('c') if !self.session.cards.is_empty() => {
+                self.list = Some(self.session.current.min(self.session.cards.len() - 1));
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_jump_keypress(&mut self, key: KeyCode) {
+        let Some(ref mut number) = self.jump else {
+            return;
+        };
+        match key {
+            KeyCode::Char(c) if c.is_ascii_digit() => number.push(c),
+            KeyCode::Backspace => {
+                number.pop();
+            }
+            KeyCode::Esc => self.jump = None,
+            KeyCode::Enter => {
+                // Cards are numbered from 1, anything out of range keeps the input open
+                match number.parse::<usize>() {
+                    Ok(n) if n >= 1 && n <= self.session.cards.len() => {
+                        self.jump = None;
+                        self.go_to(n - 1);
+                    }
+                    _ => number.clear(),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_list_keypress(&mut self, key: KeyCode) {
+        let Some(selected) = self.list else {
+            return;
+        };
+        let count = self.session.cards.len();
+        match key {
+            KeyCode::Down => self.list = Some((selected + 1) % count),
+            KeyCode::Up => self.list = Some((selected + count - 1) % count),
+            KeyCode::Enter => {
+                self.list = None;
+                self.go_to(selected);
+            }
+            KeyCode::Esc | KeyCode::Char('c') => self.list = None,
+            _ => {}
+        }
+    }
+
+    fn go_to(&mut self, index: usize) {
+        let view = (self.current_col, self.answer_visible);
+        if self.session.go_to(index) {
+            self.show_question(view);
+        }
+    }
+
+    // Start over at the question of the card the session moved to, `view` is where undo comes back to
+    fn show_question(&mut self, view: (usize, bool)) {
+        self.remember_view(view);
+        self.current_col = 0;
+        self.answer_visible = false;
+    }
+
+    fn handle_form_keypress(&mut self, key: KeyCode) {
+        let Some(ref mut form) = self.form else {
+            return;
+        };
+        let count = form.fields.len();
+        match key {
+            KeyCode::Esc => self.form = None,
+            KeyCode::Tab | KeyCode::Down if count > 0 => form.focus = (form.focus + 1) % count,
+            KeyCode::BackTab | KeyCode::Up if count > 0 => form.focus = (form.focus + count - 1) % count,
+            // Enter saves to the deck file, the form stays open if that fails
+            KeyCode::Enter => {
+                let mut fields: Vec<String> = form.fields.iter().map(|field| field.text.clone()).collect();
+                let tags = self.session.has_tags(self.session.current).then(|| fields.pop()).flatten();
+                let view = (self.current_col, self.answer_visible);
+                match self.session.edit_card(self.session.current, fields, tags) {
+                    Ok(()) => {
+                        self.form = None;
+                        self.remember_view(view);
+                    }
+                    Err(err) => form.error = Some(err.to_string()),
+                }
+            }
+            _ => {
+                if let Some(field) = form.fields.get_mut(form.focus) {
+                    field.handle_keypress(key);
+                }
+            }
+        }
+    }
+
+    // True when every answer of the current card was revealed and the next press moves on
+    fn awaiting_grade(&self) -> bool {
+        self.session.current < self.session.cards.len()
+            && !self.answer_visible
+            && self.current_col > 0
+            && self.current_col >= self.session.cards[self.session.current].len() - 1
+    }
+
+    // Record whether the card was known and move on to the next one
+    pub fn grade(&mut self, correct: bool) {
+        if !self.awaiting_grade() {
+            return;
+        }
+        self.remember_view((self.current_col, self.answer_visible));
+        self.session.grade(correct);
+        self.current_col = 0; // Back to the question column of the next card
+        self.answer_visible = false;
+    }
+
+    // Where the card was before a session action, so undo can go back to it
+    fn remember_view(&mut self, view: (usize, bool)) {
+        self.views.push(view);
+        self.redo_views.clear();
+    }
+
+    // Take back the last grade, skip or edit and show the card as it was before
+    pub fn undo(&mut self) {
+        if self.session.undo() {
+            self.redo_views.push((self.current_col, self.answer_visible));
+            (self.current_col, self.answer_visible) = self.views.pop().unwrap_or((0, false));
+        }
+    }
+
+    // Ctrl-r, do the last undone action again
+    pub fn redo(&mut self) {
+        if self.session.redo() {
+            self.views.push((self.current_col, self.answer_visible));
+            (self.current_col, self.answer_visible) = self.redo_views.pop().unwrap_or((0, false));
+        }
+    }
+
+    // Clicking the answer pane reveals the next column, same as pressing space
+    pub fn handle_mouse(&mut self, event: MouseEvent, size: Rect) {
+        if let MouseEventKind::Down(MouseButton::Left) = event.kind {
+            let answer_area = Self::popup_chunks(size)[1];
+            if contains(answer_area, event.column, event.row) {
+                self.next();
+            }
         }
     }
 
     pub fn next(&mut self) {
-        if self.current_row < self.data.len() {
+        if self.session.current < self.session.cards.len() {
             if self.answer_visible {
                 // If the answer is currently visible, hide it
                 self.answer_visible = false;
             } else {
                 // Show the answer or move to the next column
                 self.answer_visible = true;
-                if self.current_col >= self.data[self.current_row].len() - 1 {
+                if self.current_col >= self.session.cards[self.session.current].len() - 1 {
+                    self.remember_view((self.current_col, false));
                     self.current_col = 0; // Reset to the question column
                     self.answer_visible = false;
-                    self.current_row += 1; // Move to the next row
-                    if self.current_row >= self.data.len() {
-                        self.current_row = 0; // Loop back to the beginning
-                    }
+                    self.session.advance(); // Move to the next row
                 } else {
                     self.current_col += 1; // Move to the next column
                 }
@@ -81,48 +432,195 @@ impl Game {
         }
     }
 
-    pub fn draw_popup(&self, f: &mut Frame<CrosstermBackend<std::io::Stdout>>, size: Rect) {
-        // Question content with header
-        let question = if self.current_row < self.data.len() {
-            format!("{}\n\n{}", self.headers[0], self.data[self.current_row][0]) // Always show the first column as the question
+    pub fn draw_popup<B: Backend>(&self, f: &mut Frame<B>, size: Rect) {
+        if self.session.is_finished() {
+            self.draw_summary(f, size);
+            return;
+        }
+        if let Some(ref form) = self.form {
+            self.draw_form(f, size, form);
+            return;
+        }
+        if let Some(selected) = self.list {
+            self.draw_card_list(f, size, selected);
+            return;
+        }
+
+        // Question content with header, the cards of a custom study session bring their deck's headers.
+        // Columns the deck gives a language to say which one.
+        let headers = self.session.card_labels(self.session.current);
+        let question = if let (true, Some(message)) = (self.session.cards.is_empty(), &self.message) {
+            message.clone()
+        } else if self.session.cards.is_empty() {
+            "This deck has no cards".to_string()
+        } else if self.session.current < self.session.cards.len() {
+            // Always show the first column as the question, with the tags of the card below it
+            let tags = self.session.card_tags(self.session.current);
+            if tags.is_empty() {
+                format!("{}\n\n{}", headers[0], self.session.cards[self.session.current][0])
+            } else {
+                format!("{}\n\n{}\n\nTags: {}", headers[0], self.session.cards[self.session.current][0], tags.join(", "))
+            }
         } else {
             "".to_string()
         };
     
         // Answer content or header only
-        let answer_text = if self.current_row < self.data.len() && self.current_col > 0 {
+        let answer_text = if let Some(ref number) = self.jump {
+            format!("Go to card number (1-{}, Enter: go, Esc: cancel)\n\n{}_", self.session.cards.len(), number)
+        } else if self.session.current < self.session.cards.len() && self.current_col > 0 {
             if self.answer_visible {
                 // Show header and answer
-                format!("{}\n\n{}", self.headers[self.current_col], self.data[self.current_row][self.current_col])
+                format!("{}\n\n{}", headers[self.current_col], self.session.cards[self.session.current][self.current_col])
             } else {
                 // Show header only
                 let mut next_header = self.current_col + 1;
-                if next_header > self.headers.len() - 1 {
+                if next_header > headers.len() - 1 {
                     next_header = 0;
                 }
                 if next_header == 0 {
-                    "Press space for next question!".to_string()
+                    "Did you know it? Press 'y' or 'n' (space skips)".to_string()
                 }
                 else {
-                    self.headers[next_header].clone()
+                    headers[next_header].clone()
                 }
             }
         } else {
-            self.headers[self.current_col + 1].clone()
+            // One-column decks have no answer header to announce
+            headers.get(self.current_col + 1).cloned().unwrap_or_default()
         };
     
         // Create the question widget
-        let question_widget = Paragraph::new(question)
-            .block(Block::default().title("Question").borders(Borders::ALL))
-            .alignment(Alignment::Center);
+        // The number of the card, for jumping back to it, and whether it came back
+        let mut question_title = match self.session.current_card() {
+            Some(_) if self.session.is_repeat() => format!("Question {} of {} (again)", self.session.current + 1, self.session.cards.len()),
+            Some(_) => format!("Question {} of {}", self.session.current + 1, self.session.cards.len()),
+            None => String::from("Question"),
+        };
+        // Which deck the card is from when several are studied together or the deck has a name
+        let named = self.session.card_meta(self.session.current).map(|meta| meta.name.is_some()).unwrap_or(false);
+        if self.session.deck_count() > 1 || named {
+            if let Some(name) = self.session.deck_name(self.session.current) {
+                question_title = format!("{} - {}", question_title, name);
+            }
+        }
+        // Cards of Markdown decks show their emphasis and code, code blocks only line up without centering
+        let markdown = file_handler::is_markdown_deck(Path::new(self.session.card_deck(self.session.current)));
+        let text = |text: String| if markdown { rich_text::markdown(&text) } else { Text::from(text) };
+        let code = markdown && self.session.current_card().map(|card| card.iter().any(|field| field.contains("```"))).unwrap_or(false);
+        let alignment = if code { Alignment::Left } else { Alignment::Center };
+
+        let question_widget = Paragraph::new(text(question))
+            .block(Block::default().title(question_title).borders(Borders::ALL))
+            .alignment(alignment);
     
-        // Create the answer widget
-        let answer_widget = Paragraph::new(answer_text)
-            .block(Block::default().title("Answer").borders(Borders::ALL))
-            .alignment(Alignment::Center);
+        // Create the answer widget, its title says when progress couldn't be saved or a key didn't work
+        let answer_title = match self.message.as_ref().or(self.session.warnings.last()) {
+            Some(warning) => format!("Answer - {}", warning),
+            None => String::from("Answer"),
+        };
+        let answer_widget = Paragraph::new(text(answer_text))
+            .block(Block::default().title(answer_title).borders(Borders::ALL))
+            .alignment(alignment);
     
         // Split the layout
-        let chunks = tui::layout::Layout::default()
+        let chunks = Self::popup_chunks(size);
+    
+        // Render the widgets
+        f.render_widget(question_widget, chunks[0]);
+        f.render_widget(answer_widget, chunks[1]);
+    }
+
+    // The edit form takes the space of both panes, the focused field shows its cursor
+    fn draw_form<B: Backend>(&self, f: &mut Frame<B>, size: Rect, form: &CardForm) {
+        let mut lines: Vec<Spans> = form.fields.iter().enumerate().map(|(i, field)| {
+            let header = self.session.card_headers(self.session.current).get(i).map(|h| h.as_str()).unwrap_or("Tags");
+            if i == form.focus {
+                Spans::from(Span::styled(format!("{}: {}", header, field.display()), Style::default().fg(Color::Yellow)))
+            } else {
+                Spans::from(format!("{}: {}", header, field.text))
+            }
+        }).collect();
+        if let Some(ref error) = form.error {
+            lines.push(Spans::from(""));
+            lines.push(Spans::from(Span::styled(format!("Error: {}", error), Style::default().fg(Color::Red))));
+        }
+
+        let widget = Paragraph::new(lines)
+            .block(Block::default().title("Edit card (Tab: next field, Enter: save, Esc: cancel)").borders(Borders::ALL));
+        let chunks = Self::popup_chunks(size);
+        f.render_widget(widget, chunks[0].union(chunks[1]));
+    }
+
+    // Overlay with every card of the session and how it went, over both panes
+    fn draw_card_list<B: Backend>(&self, f: &mut Frame<B>, size: Rect, selected: usize) {
+        let rows: Vec<Row> = self.session.cards.iter().enumerate().map(|(i, card)| {
+            let status = self.session.card_status(i);
+            let color = match status {
+                CardStatus::Correct => Color::Green,
+                CardStatus::Failed => Color::Red,
+                CardStatus::Seen => Color::Gray,
+                CardStatus::Unseen => Color::DarkGray,
+            };
+            let question = card.first().cloned().unwrap_or_default();
+            Row::new(vec![(i + 1).to_string(), question, status.to_string()]).style(Style::default().fg(color))
+        }).collect();
+
+        let table = Table::new(rows)
+            .block(Block::default().title("Cards (Enter: go to card, Esc: close)").borders(Borders::ALL))
+            .widths(&[Constraint::Length(4), Constraint::Percentage(65), Constraint::Percentage(25)])
+            .highlight_symbol("> ");
+        let mut state = TableState::default();
+        state.select(Some(selected));
+        let chunks = Self::popup_chunks(size);
+        f.render_stateful_widget(table, chunks[0].union(chunks[1]), &mut state);
+    }
+
+    // End-of-session screen with the results and what to do next
+    fn draw_summary<B: Backend>(&self, f: &mut Frame<B>, size: Rect) {
+        let summary = self.session.summary();
+        let seconds = summary.duration.as_secs();
+        let mut lines = vec![
+            format!("Cards seen: {}", summary.seen),
+            match summary.correct_rate() {
+                Some(rate) => format!("Correct: {} of {} ({:.0}%)", summary.correct, summary.graded, rate),
+                None => String::from("Correct: nothing graded"),
+            },
+            format!("Time: {}m {:02}s", seconds / 60, seconds % 60),
+        ];
+        if !summary.hardest.is_empty() {
+            lines.push(String::new());
+            lines.push(String::from("Hardest cards:"));
+            lines.extend(summary.hardest.iter().map(|card| format!("  {}", card)));
+        }
+        if !summary.leeches.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("New leeches ('5' lists them): {}", summary.leeches.join(", ")));
+        }
+
+        if let Some(message) = self.message.as_ref().or(self.session.warnings.last()) {
+            lines.push(String::new());
+            lines.push(message.clone());
+        }
+
+        lines.push(String::new());
+        let missed = self.session.missed_cards().len();
+        if missed > 0 {
+            lines.push(format!("'r': retry the {} missed card{}", missed, if missed == 1 { "" } else { "s" }));
+        }
+        lines.push(String::from("'a': another round, 'c': list the cards, '1': back to the library"));
+
+        let widget = Paragraph::new(lines.join("\n"))
+            .block(Block::default().title("Session finished").borders(Borders::ALL))
+            .alignment(Alignment::Center);
+        // The summary takes the space of both panes
+        let chunks = Self::popup_chunks(size);
+        f.render_widget(widget, chunks[0].union(chunks[1]));
+    }
+
+    // Question pane on top, answer pane below
+    fn popup_chunks(size: Rect) -> Vec<Rect> {
+        tui::layout::Layout::default()
             .direction(tui::layout::Direction::Vertical)
             .margin(2)
             .constraints(
@@ -132,14 +630,13 @@ impl Game {
                 ]
                 .as_ref(),
             )
-            .split(size);
-    
-        // Render the widgets
-        f.render_widget(question_widget, chunks[0]);
-        f.render_widget(answer_widget, chunks[1]);
+            .split(size)
     }
-    
-    
+}
+
+// Checks if a terminal cell lies inside the given area
+pub fn contains(area: Rect, column: u16, row: u16) -> bool {
+    column >= area.x && column < area.x + area.width && row >= area.y && row < area.y + area.height
 }
 
 
@@ -147,14 +644,24 @@ impl Game {
 mod tests {
     use super::*;
 
+    // Helper to start a game on question/answer cards
+    fn game_with(cards: &[[&str; 2]]) -> Game {
+        let mut game = Game::new();
+        game.start(Session::new(
+            vec!["Question".to_string(), "Answer".to_string()],
+            cards.iter().map(|card| card.iter().map(|c| c.to_string()).collect()).collect(),
+        ));
+        game
+    }
+
     #[test]
     fn test_game_initialization() {
         let game = Game::new();
         assert_eq!(game.is_open, false);
-        assert_eq!(game.current_row, 0);
+        assert_eq!(game.session.current, 0);
         assert_eq!(game.current_col, 0);
-        assert_eq!(game.data.len(), 0);
-        assert_eq!(game.headers.len(), 0);
+        assert_eq!(game.session.cards.len(), 0);
+        assert_eq!(game.session.headers.len(), 0);
         assert_eq!(game.answer_visible, false);
     }
 
@@ -172,14 +679,9 @@ mod tests {
 
     #[test]
     fn test_next_question() {
-        let mut game = Game::new();
-        game.headers = vec!["Question".to_string(), "Answer".to_string()];
-        game.data = vec![
-            vec!["What is Rust?".to_string(), "A systems programming language.".to_string()],
-            vec!["What is Cargo?".to_string(), "Rust's package manager.".to_string()],
-        ];
+        let mut game = game_with(&[["What is Rust?", "A systems programming language."], ["What is Cargo?", "Rust's package manager."]]);
 
-        assert_eq!(game.current_row, 0);
+        assert_eq!(game.session.current, 0);
         assert_eq!(game.current_col, 0);
 
         // Simulate showing the first question's answer
@@ -192,15 +694,13 @@ mod tests {
         game.next();
         game.next();
         assert!(!game.answer_visible);
-        assert_eq!(game.current_row, 1);
+        assert_eq!(game.session.current, 1);
         assert_eq!(game.current_col, 0);
     }
 
     #[test]
     fn test_answer_visibility_toggle() {
-        let mut game = Game::new();
-        game.headers = vec!["Question".to_string(), "Answer".to_string()];
-        game.data = vec![vec!["What is Rust?".to_string(), "A systems programming language.".to_string()]];
+        let mut game = game_with(&[["What is Rust?", "A systems programming language."]]);
 
         assert!(!game.answer_visible);
 
@@ -214,10 +714,8 @@ mod tests {
     }
 
     #[test]
-    fn test_loop_back_to_start() {
-        let mut game = Game::new();
-        game.headers = vec!["Question".to_string(), "Answer".to_string()];
-        game.data = vec![vec!["What is Rust?".to_string(), "A systems programming language.".to_string()]];
+    fn test_session_ends_after_last_card() {
+        let mut game = game_with(&[["What is Rust?", "A systems programming language."]]);
 
         // Move through the single question-answer pair
         game.next();
@@ -225,9 +723,201 @@ mod tests {
         game.next();
         game.next();
 
-        // Should loop back to the first question
-        assert_eq!(game.current_row, 0);
+        // The session is over instead of looping back
+        assert!(game.session.is_finished());
+        game.next();
+        assert!(game.session.is_finished());
+    }
+
+    #[test]
+    fn test_retry_missed_and_another_round() {
+        let mut game = game_with(&[["What is Rust?", "A systems programming language."], ["What is Cargo?", "Rust's package manager."]]);
+        // The missed card comes back after the other one and is known the second time
+        for correct in ["n", "y", "y"] {
+            game.next();
+            game.next();
+            game.handle_keypress(KeyCode::Char(correct.chars().next().unwrap()));
+        }
+        assert!(game.session.is_finished());
+
+        // Only the missed card comes back
+        game.handle_keypress(KeyCode::Char('r'));
+        assert_eq!(game.session.cards.len(), 1);
+        assert_eq!(game.session.cards[0][0], "What is Rust?");
+
+        // Knowing it leaves nothing to retry, another round starts over with both cards
+        game.next();
+        game.next();
+        game.handle_keypress(KeyCode::Char('y'));
+        game.handle_keypress(KeyCode::Char('r'));
+        assert!(game.session.is_finished());
+        game.handle_keypress(KeyCode::Char('a'));
+        assert!(!game.session.is_finished());
+        assert_eq!(game.session.cards.len(), 1);
+    }
+
+    #[test]
+    fn test_another_round_error_stays_on_summary() {
+        let mut game = game_with(&[["What is Rust?", "A systems programming language."]]);
+        game.session.deck = String::from("/nonexistent/deck.csv");
+        game.next();
+        game.next();
+        game.handle_keypress(KeyCode::Char('y'));
+        assert!(game.session.is_finished());
+
+        // The deck is gone, the summary stays and says why
+        game.handle_keypress(KeyCode::Char('a'));
+        assert!(game.session.is_finished());
+        assert!(game.message.as_ref().unwrap().starts_with("Error loading CSV"));
+    }
+
+    #[test]
+    fn test_edit_current_card() {
+        let mut game = game_with(&[["What is Rust?", "A systems programming langauge."]]);
+        game.next();
+
+        game.handle_keypress(KeyCode::Char('e'));
+        assert!(game.is_typing());
+        game.handle_keypress(KeyCode::Tab);
+        for key in [KeyCode::Left, KeyCode::Left, KeyCode::Left, KeyCode::Backspace, KeyCode::Backspace, KeyCode::Char('u'), KeyCode::Char('a')] {
+            game.handle_keypress(key);
+        }
+        game.handle_keypress(KeyCode::Enter);
+
+        // The card is fixed and the answer is still showing
+        assert!(!game.is_typing());
+        assert_eq!(game.session.cards[0][1], "A systems programming language.");
+        assert!(game.answer_visible);
+
+        // Esc throws the changes away
+        game.handle_keypress(KeyCode::Char('e'));
+        game.handle_keypress(KeyCode::Char('!'));
+        game.handle_keypress(KeyCode::Esc);
+        assert_eq!(game.session.cards[0][0], "What is Rust?");
+    }
+
+    #[test]
+    fn test_structured_decks_cant_be_edited() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.json");
+        std::fs::write(&path, r#"[{"Question": "What is Rust?", "Answer": "A language"}]"#).unwrap();
+        let mut game = Game::new();
+        game.data_dir = Some(dir.path().join("data"));
+        game.start(Session::load(&dir.path().join("data"), path.to_str().unwrap(), 5, None, None, ShuffleMode::None, &mut StdRng::seed_from_u64(1)).unwrap());
+
+        // The key says why instead of opening the form, the next key clears that
+        game.handle_keypress(KeyCode::Char('e'));
+        assert!(!game.is_typing());
+        assert_eq!(game.message.as_deref(), Some(NOT_EDITABLE));
+        game.handle_keypress(KeyCode::Char(' '));
+        assert!(game.message.is_none());
+        assert!(game.session.edit_card(0, vec![String::from("What is Rust?"), String::from("A fast language")], None).is_err());
+    }
+
+    #[test]
+    fn test_undo_skip_and_grade() {
+        let mut game = game_with(&[["What is Rust?", "A systems programming language."], ["What is Cargo?", "Rust's package manager."]]);
+        game.next();
+        game.next();
+        game.handle_keypress(KeyCode::Char('y'));
+
+        // One space too many skips the second card
+        game.next();
+        game.next();
+        game.next();
+        assert!(game.session.is_finished());
+
+        // Undo brings back the grading prompt of the skipped card, then the graded one
+        game.handle_keypress(KeyCode::Char('u'));
+        assert_eq!(game.session.current, 1);
+        assert!(game.awaiting_grade());
+        game.handle_keypress(KeyCode::Char('u'));
+        assert_eq!(game.session.current, 0);
+        assert!(game.awaiting_grade());
+        assert!(game.session.progress.get("", "What is Rust?").is_none());
+
+        // Redo goes back to where the undo started from
+        game.redo();
+        assert_eq!(game.session.current, 1);
+        assert!(game.awaiting_grade());
+        assert_eq!(game.session.progress.get("", "What is Rust?").unwrap().reps, 1);
+        game.handle_keypress(KeyCode::Char('n'));
+        assert_eq!(game.session.progress.get("", "What is Cargo?").unwrap().lapses, 1);
+    }
+
+    #[test]
+    fn test_previous_jump_and_card_list() {
+        let mut game = game_with(&[["q1", "a1"], ["q2", "a2"], ["q3", "a3"]]);
+        game.next();
+        game.next();
+        game.handle_keypress(KeyCode::Char('y'));
+
+        // Back to the first card, at its question
+        game.handle_keypress(KeyCode::Left);
+        assert_eq!(game.session.current, 0);
         assert_eq!(game.current_col, 0);
+
+        // Jump to the third card by its number, out of range numbers are refused
+        game.handle_keypress(KeyCode::Char('g'));
+        assert!(game.is_typing());
+        game.handle_keypress(KeyCode::Char('7'));
+        game.handle_keypress(KeyCode::Enter);
+        assert_eq!(game.jump, Some(String::new()));
+        game.handle_keypress(KeyCode::Char('3'));
+        game.handle_keypress(KeyCode::Enter);
+        assert_eq!(game.session.current, 2);
+        assert!(!game.is_typing());
+
+        // Pick the second card from the list
+        game.handle_keypress(KeyCode::Char('c'));
+        assert_eq!(game.list, Some(2));
+        game.handle_keypress(KeyCode::Up);
+        game.handle_keypress(KeyCode::Enter);
+        assert_eq!(game.session.current, 1);
+        assert_eq!(game.list, None);
+
+        // Undo goes back through the jumps
+        game.handle_keypress(KeyCode::Char('u'));
+        assert_eq!(game.session.current, 2);
+    }
+
+    #[test]
+    fn test_click_answer_pane_reveals_answer() {
+        let mut game = game_with(&[["What is Rust?", "A systems programming language."]]);
+        let size = Rect::new(0, 0, 80, 24);
+        let click = |row| MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 40,
+            row,
+            modifiers: crossterm::event::KeyModifiers::NONE,
+        };
+
+        // Clicking the question pane does nothing
+        game.handle_mouse(click(5), size);
         assert!(!game.answer_visible);
+
+        // Clicking the answer pane reveals the answer
+        game.handle_mouse(click(18), size);
+        assert!(game.answer_visible);
+    }
+
+    #[test]
+    fn test_grade_records_progress_and_moves_on() {
+        let mut game = game_with(&[["What is Rust?", "A systems programming language."], ["What is Cargo?", "Rust's package manager."]]);
+
+        // Grading before the answers were shown does nothing
+        game.handle_keypress(KeyCode::Char('y'));
+        assert!(game.session.progress.get("", "What is Rust?").is_none());
+
+        // Reveal the answer, then hide it to get to the grading prompt
+        game.next();
+        game.next();
+        game.handle_keypress(KeyCode::Char('n'));
+
+        let progress = game.session.progress.get("", "What is Rust?").unwrap();
+        assert_eq!(progress.lapses, 1);
+        assert_eq!(game.session.current, 1);
+        assert_eq!(game.current_col, 0);
     }
 }
+
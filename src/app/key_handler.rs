@@ -1,6 +1,9 @@
-use crossterm::event::KeyCode;
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseEvent, MouseEventKind, MouseButton};
+use tui::layout::Rect;
 use crate::app::file_browser::{FileBrowser};
-use crate::app::game::{Game};
+use crate::app::game::{self, Game, Round};
+use crate::app::layout;
+use crate::app::library::Library;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppState {
@@ -10,12 +13,36 @@ pub enum AppState {
     FileBrowser,  // New state for file browser
     Exit,
     Game,
-    SelectFile
+    SelectFile,
+    Stats,
+    Leeches,
+    Editor,
 }
 
-pub fn handle_keypress(key: KeyCode, current_state: AppState, file_browser: &mut FileBrowser, game: &mut Game) -> AppState {
+pub fn handle_keypress(key: KeyCode, current_state: AppState, file_browser: &mut FileBrowser, game: &mut Game, library: &mut Library) -> AppState {
+    // While typing a filter in the file browser every key belongs to the filter input
+    if current_state == AppState::FileBrowser && file_browser.filtering {
+        return current_state;
+    }
+    // Same for renaming or exporting a deck in the library, or confirming a removal
+    if current_state == AppState::Welcome && library.is_typing() {
+        return current_state;
+    }
+    // And for the form that edits a card while studying
+    if current_state == AppState::Game && game.is_typing() {
+        return current_state;
+    }
+    // The deck editor has its own keys for everything, Esc takes it back to the library
+    if current_state == AppState::Editor {
+        return current_state;
+    }
+
     match key {
-        KeyCode::Char('1') => AppState::Welcome,
+        KeyCode::Char('1') => {
+            // Progress may have changed while playing
+            library.refresh();
+            AppState::Welcome
+        }
         KeyCode::Char('2') => {
             // Force close the fb so it works normally on reopen
             file_browser.is_open = false;
@@ -23,6 +50,15 @@ pub fn handle_keypress(key: KeyCode, current_state: AppState, file_browser: &mut
             AppState::Settings
         }
         KeyCode::Char('3') => AppState::Info,
+        KeyCode::Char('4') => {
+            library.load_dashboard();
+            AppState::Stats
+        }
+        KeyCode::Char('5') => {
+            library.message = None;
+            library.refresh();
+            AppState::Leeches
+        }
         
         // 'l' key toggles file browser state when in Settings
         KeyCode::Char('l') => {
@@ -40,9 +76,11 @@ pub fn handle_keypress(key: KeyCode, current_state: AppState, file_browser: &mut
         }
         KeyCode::Char('s') => {
             if current_state == AppState::Welcome {
+                game.filter = library.filter.clone();
                 game.toggle(file_browser);
                 if let Some(ref _selected_file) = file_browser.selected_file{
-                    if game.is_open {
+                    if game.is_open && loaded(game, library) {
+                        remember_deck(file_browser, library);
                         AppState::Game
                     } else {
                         AppState::Welcome
@@ -55,7 +93,34 @@ pub fn handle_keypress(key: KeyCode, current_state: AppState, file_browser: &mut
             }
 
         }
-        
+
+        // 'r' starts the last played deck right away
+        KeyCode::Char('r') if current_state == AppState::Welcome => {
+            match file_browser.recent_decks.entries.first().cloned() {
+                Some(deck) => start_deck(&deck, file_browser, game, library),
+                None => current_state,
+            }
+        }
+        // Enter studies the marked decks together, or else the deck highlighted in the library
+        KeyCode::Enter if current_state == AppState::Welcome && !library.marked.is_empty() => start_decks(game, library),
+        KeyCode::Enter if current_state == AppState::Welcome => {
+            match library.selected_deck().cloned() {
+                Some(deck) => start_deck(&deck, file_browser, game, library),
+                None => current_state,
+            }
+        }
+
+        // 'v' reviews everything due across decks
+        KeyCode::Char('v') if current_state == AppState::Welcome => start_due(game, library),
+
+        // 'e' opens the highlighted deck in the table editor
+        KeyCode::Char('e') if current_state == AppState::Welcome => {
+            if library.open_editor() {
+                AppState::Editor
+            } else {
+                current_state
+            }
+        }
 
         // Close the app with 'q' key
         KeyCode::Char('q') => AppState::Exit,
@@ -65,10 +130,160 @@ pub fn handle_keypress(key: KeyCode, current_state: AppState, file_browser: &mut
     }
 }
 
+// Route one terminal event through the whole app: the global keys first, then the widget of the resulting state.
+// Live input and replayed recordings both go through here, so a replay behaves exactly like the recorded session.
+pub fn handle_event(event: Event, current_state: AppState, size: Rect, file_browser: &mut FileBrowser, game: &mut Game, library: &mut Library) -> AppState {
+    match event {
+        Event::Mouse(mouse) => handle_mouse(mouse, current_state, size, file_browser, game, library),
+        // Ctrl-r redoes what 'u' undid, the components only ever see key codes
+        Event::Key(key) if key.code == KeyCode::Char('r') && key.modifiers.contains(KeyModifiers::CONTROL) => {
+            match current_state {
+                AppState::Game if !game.is_typing() => game.redo(),
+                AppState::Editor => {
+                    if let Some(editor) = library.editor.as_mut().filter(|editor| editor.input.is_none()) {
+                        editor.redo();
+                    }
+                }
+                _ => {}
+            }
+            current_state
+        }
+        Event::Key(key) => {
+            // Update the state based on key press
+            let state = handle_keypress(key.code, current_state, file_browser, game, library);
+            match state {
+                AppState::FileBrowser => file_browser.handle_keypress(key.code),
+                AppState::Game => game.handle_keypress(key.code),
+                AppState::Welcome => {
+                    library.handle_keypress(key.code);
+                    if let Some(query) = library.query_request.take() {
+                        return start_query(query, game, library);
+                    }
+                }
+                // '5' itself already opened the screen
+                AppState::Leeches if current_state == AppState::Leeches => library.handle_leech_keypress(key.code),
+                // 'e' itself only opened the editor
+                AppState::Editor if current_state == AppState::Editor => {
+                    if let Some(ref mut editor) = library.editor {
+                        editor.handle_keypress(key.code);
+                        if !editor.is_open {
+                            // Counts may have changed with the cards
+                            library.editor = None;
+                            library.refresh();
+                            return AppState::Welcome;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            state
+        }
+        _ => current_state,
+    }
+}
+
+// Start a game with a deck given by its full path
+pub fn start_deck(deck: &str, file_browser: &mut FileBrowser, game: &mut Game, library: &mut Library) -> AppState {
+    file_browser.select_path(std::path::Path::new(deck));
+
+    // Always open a fresh game, even if one was toggled open before
+    game.filter = library.filter.clone();
+    game.is_open = false;
+    game.toggle(file_browser);
+    if !loaded(game, library) {
+        return AppState::Welcome;
+    }
+    remember_deck(file_browser, library);
+    AppState::Game
+}
+
+// False if the game couldn't load its deck, the game closes again and the library says why
+fn loaded(game: &mut Game, library: &mut Library) -> bool {
+    match game.message.take() {
+        Some(err) => {
+            game.is_open = false;
+            library.message = Some(err);
+            false
+        }
+        None => true,
+    }
+}
+
+// Start a custom study session over every deck of the library, stay in the library if nothing matches
+pub fn start_query(query: trashcards::query::Query, game: &mut Game, library: &mut Library) -> AppState {
+    let empty = format!("No cards match {}", query);
+    game.start_query(library.decks.entries.clone(), query);
+    study_unless_empty(game, library, empty)
+}
+
+// Review everything due in the marked decks together, or in the whole library if none are marked
+pub fn start_due(game: &mut Game, library: &mut Library) -> AppState {
+    let decks = if library.marked.is_empty() { library.decks.entries.clone() } else { library.marked.clone() };
+    game.start_due(decks);
+    study_unless_empty(game, library, String::from("Nothing is due"))
+}
+
+// Study the marked decks together as if they were one
+pub fn start_decks(game: &mut Game, library: &mut Library) -> AppState {
+    game.filter = library.filter.clone();
+    if let Err(err) = game.start_decks(library.marked.clone()) {
+        library.message = Some(format!("Error: {}", err));
+        return AppState::Welcome;
+    }
+    study_unless_empty(game, library, String::from("No cards to study"))
+}
+
+// Go to the game if the session has cards, otherwise stay in the library and say why
+fn study_unless_empty(game: &mut Game, library: &mut Library, empty: String) -> AppState {
+    if game.session.cards.is_empty() {
+        game.round = Round::Deck;
+        game.is_open = false;
+        // A session that couldn't be drawn at all says why, so does one whose decks were all left out
+        library.message = Some(game.message.take().or_else(|| game.session.warnings.last().cloned()).unwrap_or(empty));
+        return AppState::Welcome;
+    }
+    AppState::Game
+}
+
+// Put the selected deck on top of the recent list and the library
+fn remember_deck(file_browser: &mut FileBrowser, library: &mut Library) {
+    if let Err(err) = file_browser.remember_selected_deck() {
+        library.message = Some(format!("Error saving recent decks: {}", err));
+    }
+    if let Some(path) = file_browser.selected_path() {
+        library.register(&trashcards::progress::deck_key(&path));
+    }
+}
+
+// Route a mouse event to the side menu or to the widget shown in the main area
+pub fn handle_mouse(event: MouseEvent, current_state: AppState, size: Rect, file_browser: &mut FileBrowser, game: &mut Game, library: &mut Library) -> AppState {
+    let areas = layout::compute_areas(size);
+
+    if game::contains(areas.side_menu, event.column, event.row) {
+        // Clicking a menu entry behaves exactly like pressing its key
+        if let MouseEventKind::Down(MouseButton::Left) = event.kind {
+            if let Some(key) = layout::menu_key_at(areas.side_menu, event.column, event.row) {
+                return handle_keypress(KeyCode::Char(key), current_state, file_browser, game, library);
+            }
+        }
+        return current_state;
+    }
+
+    if game::contains(areas.main, event.column, event.row) {
+        match current_state {
+            AppState::FileBrowser => file_browser.handle_mouse(event, areas.main),
+            AppState::Game => game.handle_mouse(event, areas.main),
+            _ => {}
+        }
+    }
+
+    current_state
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crossterm::event::KeyCode;
+    use crossterm::event::{KeyCode, KeyModifiers};
     use crate::app::file_browser::FileBrowser; // Make sure this path is correct for your project
 
     // Test case for pressing '1' to transition to the Welcome state
@@ -76,12 +291,13 @@ mod tests {
     fn test_handle_keypress_welcome() {
         let mut file_browser = FileBrowser::new();
         let mut game = Game::new();
+        let mut library = Library::new();
 
         // Initial state is Settings
         let current_state = AppState::Settings;
 
         // Simulate pressing '1'
-        let new_state = handle_keypress(KeyCode::Char('1'), current_state, &mut file_browser, &mut game);
+        let new_state = handle_keypress(KeyCode::Char('1'), current_state, &mut file_browser, &mut game, &mut library);
 
         // Assert that the state transitions to Welcome
         assert_eq!(new_state, AppState::Welcome);
@@ -92,12 +308,13 @@ mod tests {
     fn test_handle_keypress_settings() {
         let mut file_browser = FileBrowser::new();
         let mut game = Game::new();
+        let mut library = Library::new();
 
         // Initial state is Welcome
         let current_state = AppState::Welcome;
 
         // Simulate pressing '2'
-        let new_state = handle_keypress(KeyCode::Char('2'), current_state, &mut file_browser, &mut game);
+        let new_state = handle_keypress(KeyCode::Char('2'), current_state, &mut file_browser, &mut game, &mut library);
 
         // Assert that the state transitions to Settings
         assert_eq!(new_state, AppState::Settings);
@@ -108,28 +325,54 @@ mod tests {
     fn test_handle_keypress_info() {
         let mut file_browser = FileBrowser::new();
         let mut game = Game::new();
+        let mut library = Library::new();
 
         // Initial state is Settings
         let current_state = AppState::Settings;
 
         // Simulate pressing '3'
-        let new_state = handle_keypress(KeyCode::Char('3'), current_state, &mut file_browser, &mut game);
+        let new_state = handle_keypress(KeyCode::Char('3'), current_state, &mut file_browser, &mut game, &mut library);
 
         // Assert that the state transitions to Info
         assert_eq!(new_state, AppState::Info);
     }
 
+    // Test case for pressing '4' to open the stats dashboard
+    #[test]
+    fn test_handle_keypress_stats() {
+        let mut file_browser = FileBrowser::new();
+        let mut game = Game::new();
+        let mut library = Library::new();
+
+        let new_state = handle_keypress(KeyCode::Char('4'), AppState::Welcome, &mut file_browser, &mut game, &mut library);
+        assert_eq!(new_state, AppState::Stats);
+        assert_eq!(library.dashboard.forecast.len(), trashcards::stats::RECENT_DAYS as usize);
+    }
+
+    // Test case for pressing '5' to list the leeches
+    #[test]
+    fn test_handle_keypress_leeches() {
+        let mut file_browser = FileBrowser::new();
+        let mut game = Game::new();
+        let mut library = Library::new();
+
+        let new_state = handle_keypress(KeyCode::Char('5'), AppState::Welcome, &mut file_browser, &mut game, &mut library);
+        assert_eq!(new_state, AppState::Leeches);
+        assert!(library.leeches.is_empty());
+    }
+
     // Test case for pressing 'q' to exit the app
     #[test]
     fn test_handle_keypress_exit() {
         let mut file_browser = FileBrowser::new();
         let mut game = Game::new();
+        let mut library = Library::new();
 
         // Initial state is Info
         let current_state = AppState::Info;
 
         // Simulate pressing 'q'
-        let new_state = handle_keypress(KeyCode::Char('q'), current_state, &mut file_browser, &mut game);
+        let new_state = handle_keypress(KeyCode::Char('q'), current_state, &mut file_browser, &mut game, &mut library);
 
         // Assert that the state transitions to Exit
         assert_eq!(new_state, AppState::Exit);
@@ -140,19 +383,20 @@ mod tests {
     fn test_handle_keypress_toggle_file_browser_from_settings() {
         let mut file_browser = FileBrowser::new();
         let mut game = Game::new();
+        let mut library = Library::new();
 
         // Initial state is Settings
         let current_state = AppState::Settings;
 
         // Simulate pressing 'l' to toggle the file browser
-        let new_state = handle_keypress(KeyCode::Char('l'), current_state, &mut file_browser, &mut game);
+        let new_state = handle_keypress(KeyCode::Char('l'), current_state, &mut file_browser, &mut game, &mut library);
 
         // Assert that the state transitions to FileBrowser
         assert_eq!(new_state, AppState::FileBrowser);
         assert!(file_browser.is_open, "File browser should be open after pressing 'l'");
 
         // Simulate pressing '2' to go to the Settings
-        let new_state_after_close = handle_keypress(KeyCode::Char('2'), new_state, &mut file_browser, &mut game);
+        let new_state_after_close = handle_keypress(KeyCode::Char('2'), new_state, &mut file_browser, &mut game, &mut library);
 
         // Assert that the file browser is closed and the state remains Settings
         assert_eq!(new_state_after_close, AppState::Settings);
@@ -164,15 +408,234 @@ mod tests {
     fn test_handle_keypress_no_transition() {
         let mut file_browser = FileBrowser::new();
         let mut game = Game::new();
+        let mut library = Library::new();
 
         // Initial state is Settings
         let current_state = AppState::Settings;
 
         // Simulate pressing an unhandled key (e.g., 'x')
-        let new_state = handle_keypress(KeyCode::Char('x'), current_state, &mut file_browser, &mut game);
+        let new_state = handle_keypress(KeyCode::Char('x'), current_state, &mut file_browser, &mut game, &mut library);
 
         // Assert that the state remains the same
         assert_eq!(new_state, AppState::Settings);
     }
-}
 
+    // Test case for typing a file browser filter that contains menu keys
+    #[test]
+    fn test_handle_keypress_filtering_passes_keys_through() {
+        let mut file_browser = FileBrowser::new();
+        let mut game = Game::new();
+        let mut library = Library::new();
+        file_browser.filtering = true;
+
+        // Simulate typing 'q' into the filter
+        let new_state = handle_keypress(KeyCode::Char('q'), AppState::FileBrowser, &mut file_browser, &mut game, &mut library);
+
+        // Assert that the app does not exit
+        assert_eq!(new_state, AppState::FileBrowser);
+    }
+
+    // Test case for starting the last deck with 'r' on the Welcome screen
+    #[test]
+    fn test_handle_keypress_start_recent_deck() {
+        let mut file_browser = FileBrowser::new();
+        let mut game = Game::new();
+        let mut library = Library::new();
+
+        // Without recent decks nothing happens
+        let new_state = handle_keypress(KeyCode::Char('r'), AppState::Welcome, &mut file_browser, &mut game, &mut library);
+        assert_eq!(new_state, AppState::Welcome);
+
+        // Remember a deck and start it
+        let dir = tempfile::tempdir().unwrap();
+        game.data_dir = Some(dir.path().to_path_buf());
+        let deck = dir.path().join("deck.csv");
+        std::fs::write(&deck, "Question;Answer\nWhat is Rust?;A language\n").unwrap();
+        file_browser.recent_decks.push_front(deck.to_str().unwrap());
+
+        let new_state = handle_keypress(KeyCode::Char('r'), AppState::Welcome, &mut file_browser, &mut game, &mut library);
+        assert_eq!(new_state, AppState::Game);
+        assert!(game.is_open);
+        assert_eq!(file_browser.selected_file, Some(String::from("deck.csv")));
+        assert_eq!(file_browser.current_directory, dir.path().to_str().unwrap());
+
+        // A recent deck that is gone keeps the library open and says why
+        game.is_open = false;
+        file_browser.recent_decks.push_front(dir.path().join("gone.csv").to_str().unwrap());
+        let new_state = handle_keypress(KeyCode::Char('r'), AppState::Welcome, &mut file_browser, &mut game, &mut library);
+        assert_eq!(new_state, AppState::Welcome);
+        assert!(!game.is_open);
+        assert!(library.message.as_ref().unwrap().starts_with("Error loading CSV"));
+    }
+
+    // Test case for studying the deck highlighted in the library
+    #[test]
+    fn test_handle_keypress_study_library_deck() {
+        let mut file_browser = FileBrowser::new();
+        let mut game = Game::new();
+        let mut library = Library::new();
+
+        // Empty library, Enter does nothing
+        let new_state = handle_keypress(KeyCode::Enter, AppState::Welcome, &mut file_browser, &mut game, &mut library);
+        assert_eq!(new_state, AppState::Welcome);
+
+        let dir = tempfile::tempdir().unwrap();
+        game.data_dir = Some(dir.path().to_path_buf());
+        for name in ["a.csv", "b.csv"] {
+            std::fs::write(dir.path().join(name), "Question;Answer\nWhat is Rust?;A language\n").unwrap();
+            library.register(dir.path().join(name).to_str().unwrap());
+        }
+
+        // "b.csv" was registered last so it is on top, pick the second entry
+        library.handle_keypress(KeyCode::Down);
+        let new_state = handle_keypress(KeyCode::Enter, AppState::Welcome, &mut file_browser, &mut game, &mut library);
+        assert_eq!(new_state, AppState::Game);
+        assert_eq!(file_browser.selected_file, Some(String::from("a.csv")));
+
+        // The studied deck moves to the top of the library
+        assert!(library.decks.entries[0].ends_with("a.csv"));
+    }
+
+    // Test case for a custom study query that finds nothing
+    #[test]
+    fn test_handle_event_custom_study_without_matches() {
+        let mut file_browser = FileBrowser::new();
+        let mut game = Game::new();
+        let mut library = Library::new();
+        let size = Rect::new(0, 0, 80, 24);
+        let key = |code| Event::Key(crossterm::event::KeyEvent::new(code, KeyModifiers::NONE));
+        let dir = tempfile::tempdir().unwrap();
+        game.data_dir = Some(dir.path().to_path_buf());
+
+        let mut state = AppState::Welcome;
+        for code in [KeyCode::Char('c'), KeyCode::Char('i'), KeyCode::Char('s'), KeyCode::Char(':'), KeyCode::Char('n'), KeyCode::Char('e'), KeyCode::Char('w'), KeyCode::Enter] {
+            state = handle_event(key(code), state, size, &mut file_browser, &mut game, &mut library);
+        }
+        assert_eq!(state, AppState::Welcome);
+        assert_eq!(library.message, Some(String::from("No cards match is:new")));
+        assert_eq!(game.round, Round::Deck);
+    }
+
+    // Test case for studying marked decks together and reviewing what's due
+    #[test]
+    fn test_handle_keypress_marked_decks_and_due_review() {
+        let mut file_browser = FileBrowser::new();
+        let mut game = Game::new();
+        let mut library = Library::new();
+        let dir = tempfile::tempdir().unwrap();
+        game.data_dir = Some(dir.path().to_path_buf());
+
+        // Nothing in the library, so nothing is due
+        let new_state = handle_keypress(KeyCode::Char('v'), AppState::Welcome, &mut file_browser, &mut game, &mut library);
+        assert_eq!(new_state, AppState::Welcome);
+        assert_eq!(library.message, Some(String::from("Nothing is due")));
+
+        for (name, content) in [("fr.csv", "French;English\nLa maison;The house\n"), ("de.csv", "German;English\nDas Haus;The house\n")] {
+            std::fs::write(dir.path().join(name), content).unwrap();
+            library.register(dir.path().join(name).to_str().unwrap());
+        }
+        library.marked = library.decks.entries.clone();
+        let new_state = handle_keypress(KeyCode::Enter, AppState::Welcome, &mut file_browser, &mut game, &mut library);
+        assert_eq!(new_state, AppState::Game);
+        assert_eq!(game.session.deck_count(), 2);
+        assert_eq!(game.round, Round::Decks(library.marked.clone()));
+    }
+
+    // Test case for typing a deck name that contains menu keys
+    #[test]
+    fn test_handle_keypress_library_input_passes_keys_through() {
+        let mut file_browser = FileBrowser::new();
+        let mut game = Game::new();
+        let mut library = Library::new();
+        library.input = Some((crate::app::library::InputKind::Rename, String::new()));
+
+        let new_state = handle_keypress(KeyCode::Char('q'), AppState::Welcome, &mut file_browser, &mut game, &mut library);
+        assert_eq!(new_state, AppState::Welcome);
+    }
+
+    // Test case for editing a deck and leaving the editor again
+    #[test]
+    fn test_handle_event_deck_editor() {
+        let mut file_browser = FileBrowser::new();
+        let mut game = Game::new();
+        let mut library = Library::new();
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path().join("deck.csv");
+        std::fs::write(&deck, "Question;Answer\nWhat is Rust?;A language\n").unwrap();
+        library.register(deck.to_str().unwrap());
+
+        let size = Rect::new(0, 0, 80, 24);
+        let key = |code| Event::Key(crossterm::event::KeyEvent::new(code, KeyModifiers::NONE));
+        let state = handle_event(key(KeyCode::Char('e')), AppState::Welcome, size, &mut file_browser, &mut game, &mut library);
+        assert_eq!(state, AppState::Editor);
+
+        // Menu keys are editor keys now, 'q' doesn't quit
+        let mut state = state;
+        for code in [KeyCode::Char('a'), KeyCode::Char('q'), KeyCode::Enter, KeyCode::Char('s')] {
+            state = handle_event(key(code), state, size, &mut file_browser, &mut game, &mut library);
+        }
+        assert_eq!(state, AppState::Editor);
+        assert!(std::fs::read_to_string(&deck).unwrap().ends_with("\nq;\n"));
+
+        // 'u' takes back the question and then the new card, Ctrl-r puts both back
+        for _ in 0..2 {
+            state = handle_event(key(KeyCode::Char('u')), state, size, &mut file_browser, &mut game, &mut library);
+        }
+        assert_eq!(library.editor.as_ref().unwrap().rows.len(), 1);
+        let ctrl_r = Event::Key(crossterm::event::KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+        for _ in 0..2 {
+            state = handle_event(ctrl_r.clone(), state, size, &mut file_browser, &mut game, &mut library);
+        }
+        assert_eq!(library.editor.as_ref().unwrap().rows[1][0], "q");
+        state = handle_event(key(KeyCode::Char('s')), state, size, &mut file_browser, &mut game, &mut library);
+
+        let state = handle_event(key(KeyCode::Esc), state, size, &mut file_browser, &mut game, &mut library);
+        assert_eq!(state, AppState::Welcome);
+        assert!(library.editor.is_none());
+        assert_eq!(library.summaries[0].cards, 2);
+    }
+
+    // Test case for clicking a side menu entry
+    #[test]
+    fn test_handle_mouse_side_menu_click() {
+        let mut file_browser = FileBrowser::new();
+        let mut game = Game::new();
+        let mut library = Library::new();
+        let size = Rect::new(0, 0, 100, 30);
+        let side_menu = layout::compute_areas(size).side_menu;
+
+        // Simulate clicking the second menu entry ("2. Settings"), one row below the border
+        let click = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: side_menu.x + 2,
+            row: side_menu.y + 2,
+            modifiers: KeyModifiers::NONE,
+        };
+        let new_state = handle_mouse(click, AppState::Welcome, size, &mut file_browser, &mut game, &mut library);
+
+        // Assert that the state transitions to Settings
+        assert_eq!(new_state, AppState::Settings);
+    }
+
+    // Test case for clicking on the menu border (no transition)
+    #[test]
+    fn test_handle_mouse_side_menu_border() {
+        let mut file_browser = FileBrowser::new();
+        let mut game = Game::new();
+        let mut library = Library::new();
+        let size = Rect::new(0, 0, 100, 30);
+        let side_menu = layout::compute_areas(size).side_menu;
+
+        // Simulate clicking the top border of the menu block
+        let click = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: side_menu.x + 2,
+            row: side_menu.y,
+            modifiers: KeyModifiers::NONE,
+        };
+        let new_state = handle_mouse(click, AppState::Info, size, &mut file_browser, &mut game, &mut library);
+
+        // Assert that the state remains the same
+        assert_eq!(new_state, AppState::Info);
+    }
+}
@@ -1,9 +1,29 @@
-use tui::{Frame, backend::CrosstermBackend, widgets::{Block, Borders, Paragraph, List, ListItem}, layout::{Layout, Constraint, Direction}};
+use tui::{Frame, backend::Backend, widgets::{Block, Borders, Paragraph, List, ListItem}, layout::{Layout, Constraint, Direction, Rect}};
 use crate::app::key_handler::AppState;
 use crate::app::file_browser::FileBrowser;
 use crate::app::game::Game;
+use crate::app::library::Library;
+use crate::app::leeches;
+use crate::app::stats;
 
-pub fn draw_layout(f: &mut Frame<CrosstermBackend<std::io::Stdout>>, size: tui::layout::Rect, current_state: AppState, file_browser: &FileBrowser, game: &Game) {
+// Screen areas shared by drawing and mouse hit-testing
+pub struct LayoutAreas {
+    pub upper_bar: Rect,
+    pub side_menu: Rect,
+    pub main: Rect,
+}
+
+// Entries of the side menu, in the order they are rendered
+pub const MENU_ITEMS: [(char, &str); 6] = [
+    ('1', "1. Start Game"),
+    ('2', "2. Settings"),
+    ('3', "3. Info"),
+    ('4', "4. Stats"),
+    ('5', "5. Leeches"),
+    ('q', "q. Exit"),
+];
+
+pub fn compute_areas(size: Rect) -> LayoutAreas {
     // Define the layout with three areas: upper bar, side menu, and main content area
     let layout = Layout::default()
         .direction(Direction::Vertical)
@@ -16,6 +36,38 @@ pub fn draw_layout(f: &mut Frame<CrosstermBackend<std::io::Stdout>>, size: tui::
         )
         .split(size);
 
+    // Split the remaining space (layout[1]) horizontally into the side menu and the main content
+    let horizontal_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(20), // Side menu takes 20% of the width
+                Constraint::Percentage(80), // Main content takes 80% of the width
+            ]
+            .as_ref(),
+        )
+        .split(layout[1]);
+
+    LayoutAreas {
+        upper_bar: layout[0],
+        side_menu: horizontal_layout[0],
+        main: horizontal_layout[1],
+    }
+}
+
+// Returns the menu key of the side menu entry under the given terminal cell, if any
+pub fn menu_key_at(side_menu: Rect, column: u16, row: u16) -> Option<char> {
+    // The list is drawn inside a bordered block, so skip the border cells
+    if column <= side_menu.x || column >= side_menu.x + side_menu.width.saturating_sub(1) || row <= side_menu.y {
+        return None;
+    }
+    let index = (row - side_menu.y - 1) as usize;
+    MENU_ITEMS.get(index).map(|(key, _)| *key)
+}
+
+pub fn draw_layout<B: Backend>(f: &mut Frame<B>, size: Rect, current_state: AppState, file_browser: &FileBrowser, game: &Game, library: &Library) {
+    let areas = compute_areas(size);
+
     // Info Bar block for displaying useful information
     let info_bar_text = if let Some(ref selected_file) = file_browser.selected_file {
         // Display the full path (current directory + file name)
@@ -30,66 +82,58 @@ pub fn draw_layout(f: &mut Frame<CrosstermBackend<std::io::Stdout>>, size: tui::
         .alignment(tui::layout::Alignment::Center);
 
     // Render the upper bar at the top of the terminal
-    f.render_widget(upper_bar, layout[0]);
-
-    // Split the remaining space (layout[1]) horizontally into the side menu and the main content
-    let horizontal_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Percentage(20), // Side menu takes 20% of the width
-                Constraint::Percentage(80), // Main content takes 80% of the width
-            ]
-            .as_ref(),
-        )
-        .split(layout[1]);
+    f.render_widget(upper_bar, areas.upper_bar);
 
     // Side menu block with items
-    let side_menu = List::new(vec![
-        ListItem::new("1. Start Game"),
-        ListItem::new("2. Settings"),
-        ListItem::new("3. Info"),
-        ListItem::new("q. Exit"),
-    ])
-    .block(Block::default().borders(Borders::ALL).title("Menu"));
+    let side_menu = List::new(MENU_ITEMS.iter().map(|(_, label)| ListItem::new(*label)).collect::<Vec<ListItem>>())
+        .block(Block::default().borders(Borders::ALL).title("Menu"));
 
     // Render the side menu
-    f.render_widget(side_menu, horizontal_layout[0]);
+    f.render_widget(side_menu, areas.side_menu);
 
     // Render the main content based on the current state
     match current_state {
         AppState::Welcome => {
-            let welcome_message = Paragraph::new("Welcome to Trashcards!\nPress 's' key to start.")
-                .block(Block::default().borders(Borders::ALL).title("Welcome"));
-            f.render_widget(welcome_message, horizontal_layout[1]);
+            library.draw(f, areas.main, &file_browser.recent_decks);
         }
         AppState::Settings => {
             let settings_message = Paragraph::new("Settings widget is here! Press 'l' to select the dataset you want to use")
                 .block(Block::default().borders(Borders::ALL).title("Settings"));
-            f.render_widget(settings_message, horizontal_layout[1]);
+            f.render_widget(settings_message, areas.main);
         }
         AppState::Info => {
             let info_message = Paragraph::new("Here is some info about the app!")
                 .block(Block::default().borders(Borders::ALL).title("Info"));
-            f.render_widget(info_message, horizontal_layout[1]);
+            f.render_widget(info_message, areas.main);
         }
         AppState::FileBrowser => {
             // Only show file browser in the main content area
-            file_browser.draw_popup(f, horizontal_layout[1]);
+            file_browser.draw_popup(f, areas.main);
         }
         AppState::Game => {
-            game.draw_popup(f, horizontal_layout[1]);
+            game.draw_popup(f, areas.main);
+        }
+        AppState::Stats => {
+            stats::draw(f, areas.main, &library.dashboard);
+        }
+        AppState::Leeches => {
+            leeches::draw(f, areas.main, library);
+        }
+        AppState::Editor => {
+            if let Some(ref editor) = library.editor {
+                editor.draw(f, areas.main);
+            }
         }
         AppState::SelectFile => {
             let info_message = Paragraph::new("Select a file first in the settigns!")
                 .block(Block::default().borders(Borders::ALL).title("Info"));
-            f.render_widget(info_message, horizontal_layout[1]);
+            f.render_widget(info_message, areas.main);
         }
         AppState::Exit => {
             // Do nothing or maybe render an "Exiting" message
             let exit_message = Paragraph::new("Exiting the application...")
                 .block(Block::default().borders(Borders::ALL).title("Exit"));
-            f.render_widget(exit_message, horizontal_layout[1]);
+            f.render_widget(exit_message, areas.main);
         }
     }
 }
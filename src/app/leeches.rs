@@ -0,0 +1,43 @@
+use std::path::Path;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+    Frame,
+};
+use crate::app::library::Library;
+
+// Function to draw the leeches of every deck as the main content
+pub fn draw<B: Backend>(f: &mut Frame<B>, size: Rect, library: &Library) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+        .split(size);
+
+    let rows: Vec<Row> = library.leeches.iter().map(|leech| {
+        let deck = Path::new(&leech.deck).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let row = Row::new(vec![leech.card.clone(), deck, leech.lapses.to_string(), String::from(if leech.suspended { "suspended" } else { "" })]);
+        // Suspended leeches are out of the way, so they are dimmed
+        if leech.suspended { row.style(Style::default().fg(Color::DarkGray)) } else { row }
+    }).collect();
+
+    let title = format!("Leeches ({} cards forgotten at least {} times)", library.leeches.len(), library.leech_lapses);
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Card", "Deck", "Lapses", "Status"]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .widths(&[Constraint::Percentage(45), Constraint::Percentage(25), Constraint::Percentage(10), Constraint::Percentage(20)])
+        .highlight_style(Style::default().fg(Color::Yellow));
+    let mut state = TableState::default();
+    if !library.leeches.is_empty() {
+        state.select(Some(library.selected_leech));
+    }
+    f.render_stateful_widget(table, chunks[0], &mut state);
+
+    let text = match library.message {
+        _ if library.leeches.is_empty() => String::from("No leeches, every card sticks"),
+        Some(ref message) => message.clone(),
+        None => String::from("Space: suspend or unsuspend, 'e': rewrite the card in its deck"),
+    };
+    f.render_widget(Paragraph::new(text).block(Block::default().borders(Borders::ALL)), chunks[1]);
+}
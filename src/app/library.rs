@@ -0,0 +1,575 @@
+use crossterm::event::KeyCode;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+    Frame,
+};
+use crate::app::editor::DeckEditor;
+use trashcards::library::{self as deck_library, DeckSummary};
+use trashcards::progress::{self, ProgressStore};
+use trashcards::recent::PathList;
+use trashcards::scheduler::LEECH_LAPSES;
+use trashcards::stats::Dashboard;
+use trashcards::query::Query;
+use trashcards::tags::TagFilter;
+
+// What the text input at the bottom of the library is asking for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    Rename,
+    Export,
+    Filter,
+    Query,
+}
+
+// An action that waits for 'y' before it happens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Confirm {
+    Remove(String),     // Remove the deck from the library
+    Overwrite(PathBuf), // Export the highlighted deck over an existing file
+}
+
+// A card that was forgotten too often, listed on the Leeches screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leech {
+    pub deck: String,
+    pub card: String,
+    pub lapses: u32,
+    pub suspended: bool,
+}
+
+pub struct Library {
+    pub decks: PathList, // Registered decks, most recently played first
+    pub selected: usize,
+    pub marked: Vec<String>, // Decks marked with Space to be studied together
+    pub summaries: Vec<DeckSummary>, // Counts for each registered deck, in the same order
+    pub input: Option<(InputKind, String)>, // Text typed for a rename, export, tag filter or query
+    pub confirm: Option<Confirm>, // Asked before removing a deck or overwriting a file
+    pub query_request: Option<Query>, // Custom study query to run over every deck, picked up by the key handler
+    pub filter: Option<TagFilter>, // Sessions started from the library only draw cards whose tags match
+    pub message: Option<String>, // Feedback for the last action
+    pub edit_request: Option<PathBuf>, // Deck to open in the external editor, picked up by main
+    pub editor: Option<DeckEditor>, // Deck open in the table editor
+    pub dashboard: Dashboard,  // Review statistics shown on the Stats screen, loaded when it opens
+    pub leeches: Vec<Leech>,   // Leeches of all decks, most lapses first
+    pub selected_leech: usize,
+    pub leech_lapses: u32,     // Lapses that make a card a leech
+    data_dir: Option<PathBuf>, // Where progress and recent decks live, no dir means nothing is stored
+}
+
+impl Library {
+    pub fn new() -> Self {
+        Library {
+            decks: PathList::default(),
+            selected: 0,
+            marked: Vec::new(),
+            summaries: Vec::new(),
+            input: None,
+            confirm: None,
+            filter: None,
+            query_request: None,
+            message: None,
+            edit_request: None,
+            editor: None,
+            dashboard: Dashboard::default(),
+            leeches: Vec::new(),
+            selected_leech: 0,
+            leech_lapses: LEECH_LAPSES,
+            data_dir: None,
+        }
+    }
+
+    // Load the registered decks from the data dir
+    pub fn load(&mut self, data_dir: &Path) {
+        self.decks = deck_library::registered_decks(data_dir);
+        self.data_dir = Some(data_dir.to_path_buf());
+        self.refresh();
+    }
+
+    // Recompute the counts of every deck, progress changes while playing
+    pub fn refresh(&mut self) {
+        let store = self.progress_store();
+        let today = progress::today();
+        self.summaries = self.decks.entries.iter().map(|deck| DeckSummary::load(deck, &store, today)).collect();
+        if self.selected >= self.summaries.len() {
+            self.selected = self.summaries.len().saturating_sub(1);
+        }
+
+        self.leeches = store.leeches(self.leech_lapses).into_iter()
+            .map(|(deck, card, p)| Leech { deck: deck.to_string(), card: card.to_string(), lapses: p.lapses, suspended: p.suspended })
+            .collect();
+        if self.selected_leech >= self.leeches.len() {
+            self.selected_leech = self.leeches.len().saturating_sub(1);
+        }
+    }
+
+    // Recompute the statistics of every registered deck from the review log
+    pub fn load_dashboard(&mut self) {
+        self.refresh();
+        let new_cards = self.summaries.iter().map(|summary| summary.new as u64).sum();
+        self.dashboard = match self.data_dir {
+            Some(ref dir) => Dashboard::load(dir, new_cards),
+            None => Dashboard::new(&[], &ProgressStore::default(), new_cards, progress::today()),
+        };
+    }
+
+    fn progress_store(&self) -> ProgressStore {
+        self.data_dir.as_ref().map(|dir| ProgressStore::load(dir)).unwrap_or_default()
+    }
+
+    // Add a deck (or move it) to the top of the library
+    pub fn register(&mut self, path: &str) {
+        self.decks.push_front(path);
+        self.selected = 0;
+        self.save();
+        self.refresh();
+    }
+
+    pub fn selected_deck(&self) -> Option<&String> {
+        self.decks.entries.get(self.selected)
+    }
+
+    // Open the highlighted deck in the table editor, returns false if there is none or it can't be read
+    pub fn open_editor(&mut self) -> bool {
+        let Some(deck) = self.selected_deck().cloned() else {
+            return false;
+        };
+        match DeckEditor::open(&deck, self.data_dir.clone()) {
+            Ok(editor) => {
+                self.editor = Some(editor);
+                true
+            }
+            Err(err) => {
+                self.message = Some(format!("Error opening {}: {}", deck, err));
+                false
+            }
+        }
+    }
+
+    // True while the rename/export/filter/query input or a confirmation has focus, so typed keys go into it
+    pub fn is_typing(&self) -> bool {
+        self.input.is_some() || self.confirm.is_some()
+    }
+
+    fn save(&mut self) {
+        if let Err(err) = self.decks.save() {
+            self.message = Some(format!("Error saving library: {}", err));
+        }
+    }
+
+    pub fn handle_keypress(&mut self, key: KeyCode) {
+        if self.input.is_some() {
+            self.handle_input_keypress(key);
+            return;
+        }
+        if let Some(confirm) = self.confirm.take() {
+            // 'y' goes ahead, any other key cancels
+            if key == KeyCode::Char('y') {
+                self.message = Some(match confirm {
+                    Confirm::Remove(deck) => self.remove(&deck),
+                    Confirm::Overwrite(target) => self.export_selected(&target).unwrap_or_else(|err| format!("Error: {}", err)),
+                });
+            }
+            return;
+        }
+
+        let count = self.decks.entries.len();
+        match key {
+            KeyCode::Down if count > 0 => self.selected = (self.selected + 1) % count,
+            KeyCode::Up if count > 0 => self.selected = (self.selected + count - 1) % count,
+            // Space marks the deck to be studied together with the other marked ones
+            KeyCode::Char(' ') => {
+                if let Some(deck) = self.selected_deck().cloned() {
+                    match self.marked.iter().position(|d| *d == deck) {
+                        Some(i) => {
+                            self.marked.remove(i);
+                        }
+                        None => self.marked.push(deck),
+                    }
+                }
+            }
+            // 'E' edits the deck in $EDITOR, 'e' opens the table editor through the key handler
+            KeyCode::Char('E') => {
+                self.edit_request = self.selected_deck().map(PathBuf::from);
+            }
+            // 'm' renames (moves) the deck file
+            KeyCode::Char('m') => {
+                if let Some(name) = self.selected_deck().and_then(|deck| Path::new(deck).file_name()) {
+                    self.input = Some((InputKind::Rename, name.to_string_lossy().to_string()));
+                }
+            }
+            // 'x' exports the deck together with its progress
+            KeyCode::Char('x') => {
+                if let Some(deck) = self.selected_deck() {
+                    self.input = Some((InputKind::Export, deck_library::export_path(Path::new(deck)).to_string_lossy().to_string()));
+                }
+            }
+            // 't' sets the tag filter for the next sessions, an empty filter studies every card again
+            KeyCode::Char('t') => {
+                self.input = Some((InputKind::Filter, self.filter.as_ref().map(|f| f.to_string()).unwrap_or_default()));
+            }
+            // 'c' asks for a custom study query over all decks
+            KeyCode::Char('c') => self.input = Some((InputKind::Query, String::new())),
+            // 'd' or Delete removes the deck from the library once confirmed, the file and its progress stay
+            KeyCode::Char('d') | KeyCode::Delete => {
+                self.confirm = self.selected_deck().cloned().map(Confirm::Remove);
+            }
+            _ => {}
+        }
+    }
+
+    fn remove(&mut self, deck: &str) -> String {
+        self.decks.remove(deck);
+        self.marked.retain(|d| d != deck);
+        self.save();
+        self.refresh();
+        format!("Removed {} from the library", deck)
+    }
+
+    // Keys of the Leeches screen: pick a leech, suspend or unsuspend it, or fix it in the deck
+    pub fn handle_leech_keypress(&mut self, key: KeyCode) {
+        let count = self.leeches.len();
+        match key {
+            KeyCode::Down if count > 0 => self.selected_leech = (self.selected_leech + 1) % count,
+            KeyCode::Up if count > 0 => self.selected_leech = (self.selected_leech + count - 1) % count,
+            // Space suspends the leech or brings it back into sessions
+            KeyCode::Char(' ') => {
+                if let Some(leech) = self.leeches.get(self.selected_leech).cloned() {
+                    self.message = Some(match self.set_suspended(&leech, !leech.suspended) {
+                        Ok(()) if leech.suspended => format!("Unsuspended {}", leech.card),
+                        Ok(()) => format!("Suspended {}", leech.card),
+                        Err(err) => format!("Error: {}", err),
+                    });
+                    self.refresh();
+                }
+            }
+            // 'e' opens the deck of the leech in $EDITOR to rewrite the card
+            KeyCode::Char('e') => {
+                self.edit_request = self.leeches.get(self.selected_leech).map(|leech| PathBuf::from(&leech.deck));
+            }
+            _ => {}
+        }
+    }
+
+    fn set_suspended(&self, leech: &Leech, suspended: bool) -> Result<(), Box<dyn Error>> {
+        let mut store = self.progress_store();
+        store.set_suspended(&leech.deck, &leech.card, suspended);
+        store.save()
+    }
+
+    fn handle_input_keypress(&mut self, key: KeyCode) {
+        let Some((kind, ref mut text)) = self.input else {
+            return;
+        };
+        match key {
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Esc => self.input = None,
+            KeyCode::Enter => {
+                let text = text.clone();
+                self.input = None;
+                let result = match kind {
+                    InputKind::Rename => self.rename_selected(&text),
+                    // Exporting over an existing file asks first
+                    InputKind::Export if Path::new(&text).exists() => {
+                        self.confirm = Some(Confirm::Overwrite(PathBuf::from(text)));
+                        return;
+                    }
+                    InputKind::Export => self.export_selected(Path::new(&text)),
+                    InputKind::Filter => self.set_filter(&text),
+                    InputKind::Query => match text.parse::<Query>() {
+                        Ok(query) => {
+                            self.query_request = Some(query);
+                            return;
+                        }
+                        Err(err) => Err(err.into()),
+                    },
+                };
+                self.message = Some(match result {
+                    Ok(message) => message,
+                    Err(err) => format!("Error: {}", err),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    // Rename the deck file inside its directory, its progress and recent entry move along
+    fn rename_selected(&mut self, new_name: &str) -> Result<String, Box<dyn Error>> {
+        let Some(old) = self.selected_deck().cloned() else {
+            return Err("No deck selected".into());
+        };
+        let new = deck_library::rename_deck(self.data_dir.as_deref(), &old, new_name)?;
+
+        for entry in self.decks.entries.iter_mut().chain(self.marked.iter_mut()).filter(|e| **e == old) {
+            *entry = new.clone();
+        }
+        self.save();
+        self.refresh();
+        Ok(format!("Renamed to {}", new))
+    }
+
+    fn set_filter(&mut self, text: &str) -> Result<String, Box<dyn Error>> {
+        if text.trim().is_empty() {
+            self.filter = None;
+            return Ok(String::from("Studying all cards"));
+        }
+        let filter: TagFilter = text.parse()?;
+        let message = format!("Only studying cards tagged {}", filter);
+        self.filter = Some(filter);
+        Ok(message)
+    }
+
+    // Write the deck with its progress columns appended to another CSV file
+    fn export_selected(&self, target: &Path) -> Result<String, Box<dyn Error>> {
+        let Some(deck) = self.selected_deck() else {
+            return Err("No deck selected".into());
+        };
+        deck_library::export_deck(deck, &self.progress_store(), target)?;
+        Ok(format!("Exported to {}", target.display()))
+    }
+
+    // Function to draw the library as the main content of the Welcome screen.
+    // Playing a deck moves it to the top, so the library doubles as the list of recent decks.
+    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, size: Rect, recent: &PathList) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(5), Constraint::Min(3), Constraint::Length(3)].as_ref())
+            .split(size);
+
+        let welcome_message = Paragraph::new("Enter: study the highlighted deck, Space: mark decks to study together, 'v': review everything due\n's': study the selected file, 'r': play the last deck again, 'c': custom study, 't': filter by tags\n'e': edit, 'E': edit in $EDITOR, 'm': rename, 'x': export, 'd': remove from the library")
+            .block(Block::default().borders(Borders::ALL).title("Welcome to Trashcards!"));
+        f.render_widget(welcome_message, chunks[0]);
+
+        let rows: Vec<Row> = self.summaries.iter().map(|summary| {
+            // Decks with a name of their own are listed by it
+            let name = match summary.meta.name {
+                Some(ref name) => name.clone(),
+                None => Path::new(&summary.path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            };
+            let name = if self.marked.contains(&summary.path) { format!("* {}", name) } else { name };
+            // The deck 'r' plays again
+            let name = if recent.entries.first() == Some(&summary.path) { format!("{} (last)", name) } else { name };
+            match summary.error {
+                Some(ref err) => Row::new(vec![name, format!("unreadable: {}", err)]).style(Style::default().fg(Color::DarkGray)),
+                None => Row::new(vec![
+                    name,
+                    summary.cards.to_string(),
+                    summary.new.to_string(),
+                    summary.due.to_string(),
+                    summary.learned.to_string(),
+                    summary.last_studied.map(progress::format_day).unwrap_or_else(|| String::from("never")),
+                ]),
+            }
+        }).collect();
+
+        let table = Table::new(rows)
+            .header(Row::new(vec!["Deck", "Cards", "New", "Due", "Learned", "Last studied"]).style(Style::default().add_modifier(Modifier::BOLD)))
+            .block(Block::default().borders(Borders::ALL).title(match self.filter {
+                Some(ref filter) => format!("Library ({} decks, only cards tagged {})", self.summaries.len(), filter),
+                None => format!("Library ({} decks, last played first)", self.summaries.len()),
+            }))
+            .widths(&[
+                Constraint::Percentage(40),
+                Constraint::Percentage(10),
+                Constraint::Percentage(10),
+                Constraint::Percentage(10),
+                Constraint::Percentage(10),
+                Constraint::Percentage(20),
+            ])
+            .highlight_style(Style::default().fg(Color::Yellow));
+        let mut state = TableState::default();
+        if !self.summaries.is_empty() {
+            state.select(Some(self.selected));
+        }
+        f.render_stateful_widget(table, chunks[1], &mut state);
+
+        // Text input while renaming or exporting, otherwise the result of the last action or what the highlighted deck is about
+        let (title, text) = match (&self.input, &self.confirm) {
+            (Some((InputKind::Rename, text)), _) => ("New file name (Enter: rename, Esc: cancel)", format!("{}_", text)),
+            (Some((InputKind::Export, text)), _) => ("Export to (Enter: export, Esc: cancel)", format!("{}_", text)),
+            (Some((InputKind::Filter, text)), _) => ("Study cards tagged, e.g. networking and not basics (Enter: set, empty: all cards)", format!("{}_", text)),
+            (Some((InputKind::Query, text)), _) => ("Study cards of all decks matching, e.g. due:today lapses>3 (Enter: study, Esc: cancel)", format!("{}_", text)),
+            (None, Some(Confirm::Remove(deck))) => ("Remove from the library? (y: remove, any other key: keep it)", deck.clone()),
+            (None, Some(Confirm::Overwrite(target))) => ("File exists, overwrite it? (y: overwrite, any other key: cancel)", target.display().to_string()),
+            (None, None) if self.summaries.is_empty() => ("", String::from("No decks yet, pick one in the settings with '2' > 'l'")),
+            (None, None) => match (self.message.clone(), self.summaries.get(self.selected).map(|summary| &summary.meta)) {
+                (Some(message), _) => ("", message),
+                (None, Some(meta)) => {
+                    let author = meta.author.as_ref().map(|author| format!("by {}", author));
+                    ("", [meta.description.clone(), author].into_iter().flatten().collect::<Vec<String>>().join(" - "))
+                }
+                (None, None) => ("", String::new()),
+            },
+        };
+        f.render_widget(Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(title)), chunks[2]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // Helper to create a library with one deck in a temporary data dir
+    fn library_with_deck() -> (tempfile::TempDir, Library, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path().join("fr.csv");
+        fs::write(&deck, "French;English\nLa maison;The house\nLe chien;The dog\nLa voiture;The car\n").unwrap();
+        let deck = progress::deck_key(&deck);
+
+        let mut library = Library::new();
+        library.load(dir.path());
+        library.register(&deck);
+        (dir, library, deck)
+    }
+
+    #[test]
+    fn test_register_and_remove() {
+        let (dir, mut library, deck) = library_with_deck();
+        assert_eq!(library.decks.entries, vec![deck.clone()]);
+
+        // The library is stored in the data dir
+        let mut reloaded = Library::new();
+        reloaded.load(dir.path());
+        assert_eq!(reloaded.decks.entries, vec![deck.clone()]);
+
+        // Removing asks first and only drops the entry
+        library.handle_keypress(KeyCode::Char('d'));
+        assert_eq!(library.confirm, Some(Confirm::Remove(deck.clone())));
+        library.handle_keypress(KeyCode::Char('n'));
+        assert_eq!(library.decks.entries, vec![deck.clone()]);
+        library.handle_keypress(KeyCode::Char('d'));
+        library.handle_keypress(KeyCode::Char('y'));
+        assert!(library.decks.entries.is_empty());
+        assert!(Path::new(&deck).exists());
+    }
+
+    #[test]
+    fn test_mark_decks() {
+        let (dir, mut library, deck) = library_with_deck();
+        let other = dir.path().join("de.csv");
+        fs::write(&other, "German;English\nDas Haus;The house\n").unwrap();
+        let other = progress::deck_key(&other);
+        library.register(&other);
+
+        // Space marks and unmarks the highlighted deck, removing a deck drops its mark
+        library.handle_keypress(KeyCode::Char(' '));
+        library.handle_keypress(KeyCode::Down);
+        library.handle_keypress(KeyCode::Char(' '));
+        assert_eq!(library.marked, vec![other.clone(), deck.clone()]);
+        library.handle_keypress(KeyCode::Char(' '));
+        assert_eq!(library.marked, vec![other.clone()]);
+        library.handle_keypress(KeyCode::Up);
+        library.handle_keypress(KeyCode::Char('d'));
+        library.handle_keypress(KeyCode::Char('y'));
+        assert!(library.marked.is_empty());
+    }
+
+    #[test]
+    fn test_tag_filter() {
+        let (_dir, mut library, _deck) = library_with_deck();
+        library.handle_keypress(KeyCode::Char('t'));
+        assert!(library.is_typing());
+        for c in "verbs and".chars() {
+            library.handle_keypress(KeyCode::Char(c));
+        }
+        library.handle_keypress(KeyCode::Enter);
+        assert!(library.filter.is_none());
+        assert!(library.message.as_ref().unwrap().starts_with("Error"));
+
+        // The input starts with the current filter, emptying it studies every card again
+        library.handle_keypress(KeyCode::Char('t'));
+        for c in "verbs and not basics".chars() {
+            library.handle_keypress(KeyCode::Char(c));
+        }
+        library.handle_keypress(KeyCode::Enter);
+        assert_eq!(library.filter.as_ref().unwrap().to_string(), "verbs and not basics");
+        library.handle_keypress(KeyCode::Char('t'));
+        assert_eq!(library.input.as_ref().unwrap().1, "verbs and not basics");
+        for _ in 0..20 {
+            library.handle_keypress(KeyCode::Backspace);
+        }
+        library.handle_keypress(KeyCode::Enter);
+        assert!(library.filter.is_none());
+    }
+
+    #[test]
+    fn test_rename_keeps_progress() {
+        let (dir, mut library, deck) = library_with_deck();
+        let mut store = ProgressStore::load(dir.path());
+        store.record(&deck, "Le chien", true, 10);
+        store.save().unwrap();
+
+        // Type a new name over the suggested one
+        library.handle_keypress(KeyCode::Char('m'));
+        assert!(library.is_typing());
+        library.input = Some((InputKind::Rename, String::new()));
+        for c in "french.csv".chars() {
+            library.handle_keypress(KeyCode::Char(c));
+        }
+        library.handle_keypress(KeyCode::Enter);
+
+        let new = progress::deck_key(&dir.path().join("french.csv"));
+        assert_eq!(library.decks.entries, vec![new.clone()]);
+        assert!(!Path::new(&deck).exists());
+        assert!(ProgressStore::load(dir.path()).get(&new, "Le chien").is_some());
+    }
+
+    #[test]
+    fn test_leeches_can_be_suspended() {
+        let (dir, mut library, deck) = library_with_deck();
+        let mut store = ProgressStore::load(dir.path());
+        for _ in 0..LEECH_LAPSES {
+            store.record(&deck, "Le chien", false, 10);
+        }
+        store.record(&deck, "La maison", false, 10);
+        store.save().unwrap();
+
+        library.refresh();
+        assert_eq!(library.leeches.len(), 1);
+        assert_eq!(library.leeches[0].card, "Le chien");
+
+        library.handle_leech_keypress(KeyCode::Char(' '));
+        assert!(library.leeches[0].suspended);
+        assert!(ProgressStore::load(dir.path()).get(&deck, "Le chien").unwrap().suspended);
+        library.handle_leech_keypress(KeyCode::Char(' '));
+        assert!(!library.leeches[0].suspended);
+
+        library.handle_leech_keypress(KeyCode::Char('e'));
+        assert_eq!(library.edit_request, Some(PathBuf::from(&deck)));
+    }
+
+    #[test]
+    fn test_export_with_progress() {
+        let (dir, mut library, _deck) = library_with_deck();
+
+        // The export path is suggested next to the deck
+        library.handle_keypress(KeyCode::Char('x'));
+        assert_eq!(library.input, Some((InputKind::Export, dir.path().join("fr-export.csv").to_string_lossy().to_string())));
+        library.handle_keypress(KeyCode::Enter);
+
+        assert!(dir.path().join("fr-export.csv").exists());
+        assert!(library.message.as_ref().unwrap().starts_with("Exported to"));
+
+        // Exporting again asks before overwriting the file
+        fs::write(dir.path().join("fr-export.csv"), "keep me").unwrap();
+        library.handle_keypress(KeyCode::Char('x'));
+        library.handle_keypress(KeyCode::Enter);
+        assert!(library.is_typing());
+        library.handle_keypress(KeyCode::Esc);
+        assert_eq!(fs::read_to_string(dir.path().join("fr-export.csv")).unwrap(), "keep me");
+
+        library.handle_keypress(KeyCode::Char('x'));
+        library.handle_keypress(KeyCode::Enter);
+        library.handle_keypress(KeyCode::Char('y'));
+        assert_ne!(fs::read_to_string(dir.path().join("fr-export.csv")).unwrap(), "keep me");
+        assert!(library.message.as_ref().unwrap().starts_with("Exported to"));
+    }
+}
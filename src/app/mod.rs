@@ -2,5 +2,13 @@
 pub mod layout;
 pub mod key_handler;
 pub mod file_browser;
-pub mod file_handler;
-pub mod game;
\ No newline at end of file
+pub mod game;
+pub mod editor;
+pub mod library;
+pub mod leeches;
+pub mod replay;
+pub mod rich_text;
+pub mod stats;
+
+#[cfg(test)]
+mod snapshots;
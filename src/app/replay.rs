@@ -0,0 +1,387 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tempfile::TempDir;
+use trashcards::session::ShuffleMode;
+
+// Recordings are plain text with one event per line, so they can be read, trimmed and attached to bug reports:
+//
+//   arg study
+//   arg deck.csv
+//   seed 1234
+//   shuffle full
+//   resize 80 24
+//   key 2
+//   key l
+//   key Down
+//   key Ctrl-r
+//   mouse down-left 12 7
+//
+// The arg lines are the command line the app was started with, a replay starts the same way.
+// Empty lines and lines starting with '#' are skipped.
+
+// A recording loaded from a file
+#[derive(Debug, Default, PartialEq)]
+pub struct Script {
+    pub seed: Option<u64>, // Seed of the card RNG while the recording was made
+    pub shuffle: Option<ShuffleMode>,
+    pub command: Vec<String>, // Arguments after the program name, without --record and --replay
+    pub events: Vec<Event>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut script = Script::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(arg) = line.strip_prefix("arg ") {
+                script.command.push(arg.to_string());
+                continue;
+            }
+            if let Some(seed) = line.strip_prefix("seed ") {
+                script.seed = Some(seed.trim().parse().map_err(|_| format!("line {}: bad seed {:?}", number + 1, seed))?);
+                continue;
+            }
+            if let Some(shuffle) = line.strip_prefix("shuffle ") {
+                script.shuffle = Some(shuffle.trim().parse().map_err(|err| format!("line {}: {}", number + 1, err))?);
+                continue;
+            }
+            let event = parse_event(line).map_err(|err| format!("line {}: {}", number + 1, err))?;
+            script.events.push(event);
+        }
+        Ok(script)
+    }
+}
+
+// Writes every event of a live session to a file, flushed per event so a crash still leaves a usable recording
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    // The terminal size goes first, mouse clicks only make sense with the layout they were made on
+    pub fn create(path: &Path, command: &[String], seed: u64, shuffle: ShuffleMode, width: u16, height: u16) -> Result<Self, Box<dyn Error>> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "# trashcards recording, replay with: trashcards --replay {}", path.display())?;
+        for arg in command {
+            writeln!(out, "arg {}", arg)?;
+        }
+        writeln!(out, "seed {}", seed)?;
+        writeln!(out, "shuffle {}", shuffle)?;
+        writeln!(out, "resize {} {}", width, height)?;
+        Ok(Recorder { out })
+    }
+
+    pub fn record(&mut self, event: &Event) -> Result<(), Box<dyn Error>> {
+        if let Some(line) = format_event(event) {
+            writeln!(self.out, "{}", line)?;
+            self.out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+// The command line to put in a recording, leaving out the recording options themselves
+pub fn recorded_command(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut command = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--record" | "--replay" => {
+                args.next();
+            }
+            _ if arg.starts_with("--record=") || arg.starts_with("--replay=") => {}
+            _ => command.push(arg),
+        }
+    }
+    command
+}
+
+// Replays run in a copy of the data dir, so replaying a bug report doesn't grade cards or move decks for real
+pub fn scratch_data_dir(data_dir: &Path) -> Result<TempDir, Box<dyn Error>> {
+    let scratch = tempfile::tempdir()?;
+    if data_dir.is_dir() {
+        for entry in fs::read_dir(data_dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                fs::copy(entry.path(), scratch.path().join(entry.file_name()))?;
+            }
+        }
+    }
+    Ok(scratch)
+}
+
+// One line for an event, None for events that don't change anything (mouse moves, focus changes)
+pub fn format_event(event: &Event) -> Option<String> {
+    match event {
+        Event::Key(key) => {
+            let mut line = String::from("key ");
+            if key.modifiers.contains(KeyModifiers::CONTROL) {
+                line.push_str("Ctrl-");
+            }
+            if key.modifiers.contains(KeyModifiers::ALT) {
+                line.push_str("Alt-");
+            }
+            line.push_str(&format_key(key.code)?);
+            Some(line)
+        }
+        Event::Mouse(mouse) => {
+            let kind = match mouse.kind {
+                MouseEventKind::Down(button) => format!("down-{}", format_button(button)),
+                MouseEventKind::Up(button) => format!("up-{}", format_button(button)),
+                MouseEventKind::Drag(button) => format!("drag-{}", format_button(button)),
+                MouseEventKind::ScrollUp => String::from("scroll-up"),
+                MouseEventKind::ScrollDown => String::from("scroll-down"),
+                MouseEventKind::Moved => return None,
+            };
+            Some(format!("mouse {} {} {}", kind, mouse.column, mouse.row))
+        }
+        Event::Resize(width, height) => Some(format!("resize {} {}", width, height)),
+        _ => None,
+    }
+}
+
+fn format_key(code: KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Char(' ') => "Space",
+        KeyCode::Char(c) => return Some(c.to_string()),
+        KeyCode::F(n) => return Some(format!("F{}", n)),
+        KeyCode::Enter => "Enter",
+        KeyCode::Esc => "Esc",
+        KeyCode::Backspace => "Backspace",
+        KeyCode::Tab => "Tab",
+        KeyCode::BackTab => "BackTab",
+        KeyCode::Delete => "Delete",
+        KeyCode::Insert => "Insert",
+        KeyCode::Up => "Up",
+        KeyCode::Down => "Down",
+        KeyCode::Left => "Left",
+        KeyCode::Right => "Right",
+        KeyCode::Home => "Home",
+        KeyCode::End => "End",
+        KeyCode::PageUp => "PageUp",
+        KeyCode::PageDown => "PageDown",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+fn format_button(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Right => "right",
+        MouseButton::Middle => "middle",
+    }
+}
+
+pub fn parse_event(line: &str) -> Result<Event, String> {
+    let (kind, rest) = line.split_once(' ').ok_or_else(|| format!("incomplete event {:?}", line))?;
+    match kind {
+        "key" => parse_key(rest).map(Event::Key),
+        "mouse" => {
+            let parts: Vec<&str> = rest.split_whitespace().collect();
+            let [kind, column, row] = parts[..] else {
+                return Err(format!("expected 'mouse KIND COLUMN ROW', got {:?}", line));
+            };
+            let kind = match kind.split_once('-') {
+                Some(("scroll", "up")) => MouseEventKind::ScrollUp,
+                Some(("scroll", "down")) => MouseEventKind::ScrollDown,
+                Some(("down", button)) => MouseEventKind::Down(parse_button(button)?),
+                Some(("up", button)) => MouseEventKind::Up(parse_button(button)?),
+                Some(("drag", button)) => MouseEventKind::Drag(parse_button(button)?),
+                _ => return Err(format!("unknown mouse event {:?}", kind)),
+            };
+            Ok(Event::Mouse(MouseEvent {
+                kind,
+                column: parse_number(column)?,
+                row: parse_number(row)?,
+                modifiers: KeyModifiers::NONE,
+            }))
+        }
+        "resize" => {
+            let (width, height) = rest.trim().split_once(' ').ok_or("expected 'resize WIDTH HEIGHT'")?;
+            Ok(Event::Resize(parse_number(width)?, parse_number(height)?))
+        }
+        _ => Err(format!("unknown event {:?}", kind)),
+    }
+}
+
+fn parse_key(text: &str) -> Result<KeyEvent, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = text.trim();
+    loop {
+        if let Some(rest) = name.strip_prefix("Ctrl-").filter(|rest| !rest.is_empty()) {
+            modifiers |= KeyModifiers::CONTROL;
+            name = rest;
+        } else if let Some(rest) = name.strip_prefix("Alt-").filter(|rest| !rest.is_empty()) {
+            modifiers |= KeyModifiers::ALT;
+            name = rest;
+        } else {
+            break;
+        }
+    }
+
+    let code = match name {
+        "Space" => KeyCode::Char(' '),
+        "Enter" => KeyCode::Enter,
+        "Esc" => KeyCode::Esc,
+        "Backspace" => KeyCode::Backspace,
+        "Tab" => KeyCode::Tab,
+        "BackTab" => KeyCode::BackTab,
+        "Delete" => KeyCode::Delete,
+        "Insert" => KeyCode::Insert,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        _ if name.chars().count() == 1 => KeyCode::Char(name.chars().next().unwrap()),
+        _ if name.starts_with('F') => KeyCode::F(name[1..].parse().map_err(|_| format!("unknown key {:?}", name))?),
+        _ => return Err(format!("unknown key {:?}", name)),
+    };
+    Ok(KeyEvent::new(code, modifiers))
+}
+
+fn parse_button(name: &str) -> Result<MouseButton, String> {
+    match name {
+        "left" => Ok(MouseButton::Left),
+        "right" => Ok(MouseButton::Right),
+        "middle" => Ok(MouseButton::Middle),
+        _ => Err(format!("unknown mouse button {:?}", name)),
+    }
+}
+
+fn parse_number(text: &str) -> Result<u16, String> {
+    text.trim().parse().map_err(|_| format!("{:?} is not a number", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::file_browser::FileBrowser;
+    use crate::app::game::{self, Game};
+    use crate::app::key_handler::{self, AppState};
+    use crate::app::library::Library;
+    use rand::{rngs::StdRng, SeedableRng};
+    use tui::layout::Rect;
+
+    #[test]
+    fn test_format_and_parse_roundtrip() {
+        let events = [
+            Event::Key(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE)),
+            Event::Key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE)),
+            Event::Key(KeyEvent::new(KeyCode::Char('-'), KeyModifiers::NONE)),
+            Event::Key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL)),
+            Event::Key(KeyEvent::new(KeyCode::PageDown, KeyModifiers::NONE)),
+            Event::Key(KeyEvent::new(KeyCode::F(5), KeyModifiers::NONE)),
+            Event::Mouse(MouseEvent { kind: MouseEventKind::Down(MouseButton::Left), column: 12, row: 7, modifiers: KeyModifiers::NONE }),
+            Event::Mouse(MouseEvent { kind: MouseEventKind::ScrollDown, column: 3, row: 4, modifiers: KeyModifiers::NONE }),
+            Event::Resize(80, 24),
+        ];
+        for event in events {
+            let line = format_event(&event).unwrap();
+            assert_eq!(parse_event(&line), Ok(event), "{}", line);
+        }
+
+        // Mouse moves are left out of recordings
+        let moved = Event::Mouse(MouseEvent { kind: MouseEventKind::Moved, column: 1, row: 1, modifiers: KeyModifiers::NONE });
+        assert_eq!(format_event(&moved), None);
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let script = Script::parse("# comment\nseed 42\n\nkey 2\nkey Ctrl-r\nresize 100 30\n").unwrap();
+        assert_eq!(script.seed, Some(42));
+        assert_eq!(script.events.len(), 3);
+
+        let err = Script::parse("key 2\nkey NotAKey\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"));
+    }
+
+    #[test]
+    fn test_record_then_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.txt");
+        let command = recorded_command(["study", "my deck.csv", "--record", "session.txt", "--size=5"].map(String::from));
+        assert_eq!(command, vec!["study", "my deck.csv", "--size=5"]);
+        let mut recorder = Recorder::create(&path, &command, 9, ShuffleMode::Buckets, 80, 24).unwrap();
+        recorder.record(&Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))).unwrap();
+        drop(recorder);
+
+        let script = Script::load(&path).unwrap();
+        assert_eq!(script.command, command);
+        assert_eq!(script.seed, Some(9));
+        assert_eq!(script.shuffle, Some(ShuffleMode::Buckets));
+        assert_eq!(script.events, vec![Event::Resize(80, 24), Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))]);
+    }
+
+    #[test]
+    fn test_scratch_data_dir_is_a_copy() {
+        let data_dir = tempfile::tempdir().unwrap();
+        fs::write(data_dir.path().join("progress.csv"), "deck;question\n").unwrap();
+
+        let scratch = scratch_data_dir(data_dir.path()).unwrap();
+        fs::write(scratch.path().join("progress.csv"), "changed").unwrap();
+        assert_eq!(fs::read_to_string(data_dir.path().join("progress.csv")).unwrap(), "deck;question\n");
+
+        // A first run has no data dir yet
+        assert!(scratch_data_dir(&data_dir.path().join("missing")).is_ok());
+    }
+
+    // Play a script through the same event routing as the app and return the state it ends in
+    fn replay(script: &Script, file_browser: &mut FileBrowser, game: &mut Game, library: &mut Library) -> AppState {
+        game.rng = StdRng::seed_from_u64(script.seed.unwrap_or_default());
+        let mut size = Rect::new(0, 0, 80, 24);
+        let mut state = AppState::Welcome;
+        for event in &script.events {
+            if let Event::Resize(width, height) = *event {
+                size = Rect::new(0, 0, width, height);
+            }
+            state = key_handler::handle_event(event.clone(), state, size, file_browser, game, library);
+        }
+        state
+    }
+
+    #[test]
+    fn test_replay_is_deterministic() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = tempfile::tempdir().unwrap();
+        let mut deck = String::from("Question;Answer\n");
+        for i in 0..30 {
+            deck.push_str(&format!("q{};a{}\n", i, i));
+        }
+        fs::write(dir.path().join("deck.csv"), deck).unwrap();
+
+        // Open the settings, browse to the deck with the keyboard, pick it, start playing and reveal the answer
+        let script = Script::parse("seed 1234\nresize 80 24\nkey 2\nkey l\nkey Down\nkey Enter\nkey 1\nkey s\nkey Space\n").unwrap();
+        let run = || {
+            let mut file_browser = FileBrowser::new();
+            file_browser.current_directory = dir.path().to_str().unwrap().to_string();
+            let mut game = Game::new();
+            game.data_dir = Some(data_dir.path().to_path_buf());
+            let mut library = Library::new();
+            let state = replay(&script, &mut file_browser, &mut game, &mut library);
+            (state, file_browser.selected_file.clone(), game.session.cards.clone())
+        };
+
+        let (state, selected, cards) = run();
+        assert_eq!(state, AppState::Game);
+        assert_eq!(selected, Some(String::from("deck.csv")));
+        assert_eq!(cards.len(), game::DEFAULT_SESSION_SIZE);
+
+        // Same seed, same cards
+        assert_eq!(run(), (state, selected, cards));
+    }
+}
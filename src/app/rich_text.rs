@@ -0,0 +1,114 @@
+use tui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans, Text},
+};
+
+// Markers of inline styles, longer ones first so "**" isn't read as two "*"
+const MARKERS: [&str; 5] = ["`", "**", "__", "*", "_"];
+
+// Card text of a Markdown deck: code blocks lose their fences and keep their lines as they are,
+// `code`, **bold** and *emphasis* are styled instead of shown with their markers
+pub fn markdown(text: &str) -> Text<'static> {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        } else if in_code {
+            lines.push(Spans::from(Span::styled(line.to_string(), code_style())));
+        } else {
+            lines.push(inline(line));
+        }
+    }
+    Text::from(lines)
+}
+
+fn code_style() -> Style {
+    Style::default().fg(Color::Cyan)
+}
+
+// Styled spans of one line, markers without a partner stay as they are
+fn inline(line: &str) -> Spans<'static> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let styled = MARKERS.iter().filter(|marker| rest.starts_with(**marker)).find_map(|marker| styled(rest, marker, plain.chars().last()));
+        match styled {
+            Some((span, after)) => {
+                if !plain.is_empty() {
+                    spans.push(Span::raw(std::mem::take(&mut plain)));
+                }
+                spans.push(span);
+                rest = after;
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::raw(plain));
+    }
+    Spans::from(spans)
+}
+
+// The span starting with a marker and the text after it, if the marker is closed again.
+// Underscores only count at word boundaries, so snake_case_names stay as they are.
+fn styled<'a>(text: &'a str, marker: &str, before: Option<char>) -> Option<(Span<'static>, &'a str)> {
+    let inner_start = &text[marker.len()..];
+    let end = inner_start.find(marker)?;
+    let (inner, after) = (&inner_start[..end], &inner_start[end + marker.len()..]);
+    if inner.is_empty() || (marker != "`" && (inner.starts_with(' ') || inner.ends_with(' '))) {
+        return None;
+    }
+    let boundary = |c: Option<char>| !c.map(char::is_alphanumeric).unwrap_or(false);
+    if marker.starts_with('_') && !(boundary(before) && boundary(after.chars().next())) {
+        return None;
+    }
+
+    let style = match marker {
+        "`" => code_style(),
+        "**" | "__" => Style::default().add_modifier(Modifier::BOLD),
+        _ => Style::default().add_modifier(Modifier::ITALIC),
+    };
+    Some((Span::styled(inner.to_string(), style), after))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Text and style of every span of a line
+    fn spans(line: &str) -> Vec<(String, Style)> {
+        inline(line).0.into_iter().map(|span| (span.content.to_string(), span.style)).collect()
+    }
+
+    #[test]
+    fn test_inline_styles() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let italic = Style::default().add_modifier(Modifier::ITALIC);
+        assert_eq!(spans("A **systems** language, see `cargo` or _the book_"), vec![
+            (String::from("A "), Style::default()),
+            (String::from("systems"), bold),
+            (String::from(" language, see "), Style::default()),
+            (String::from("cargo"), code_style()),
+            (String::from(" or "), Style::default()),
+            (String::from("the book"), italic),
+        ]);
+
+        // Nothing to style
+        for line in ["snake_case_name", "2 * 3 * 4", "a lonely *", "number_"] {
+            assert_eq!(spans(line), vec![(line.to_string(), Style::default())], "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_code_blocks_keep_their_lines() {
+        let text = markdown("Print it:\n```rust\nfn main() {\n    println!(\"*hi*\");\n}\n```\nDone");
+        let lines: Vec<String> = text.lines.iter().map(|line| line.0.iter().map(|span| span.content.to_string()).collect()).collect();
+        assert_eq!(lines, vec!["Print it:", "fn main() {", "    println!(\"*hi*\");", "}", "Done"]);
+        assert_eq!(text.lines[2].0[0].style, code_style());
+    }
+}
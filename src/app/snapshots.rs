@@ -0,0 +1,273 @@
+// Snapshot tests: every screen is drawn into a TestBackend and compared with a golden file
+// in tests/snapshots. Run with UPDATE_SNAPSHOTS=1 to rewrite the golden files after a UI change.
+use std::fs;
+use std::path::PathBuf;
+use crossterm::event::KeyCode;
+use tui::{backend::TestBackend, buffer::Buffer, Terminal};
+use trashcards::file_handler::{CsvStyle, DeckMeta};
+use trashcards::library::DeckSummary;
+use trashcards::progress::ProgressStore;
+use trashcards::reviews::Review;
+use trashcards::session::{Session, ShuffleMode};
+use trashcards::stats::Dashboard;
+use crate::app::editor::DeckEditor;
+use crate::app::file_browser::FileBrowser;
+use crate::app::game::Game;
+use crate::app::key_handler::AppState;
+use crate::app::layout;
+use crate::app::library::{Leech, Library};
+
+const WIDTH: u16 = 80;
+const HEIGHT: u16 = 24;
+
+// Draw the whole layout for a state and return the screen as plain text, one line per row
+fn render(state: AppState, file_browser: &FileBrowser, game: &Game, library: &Library) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+    terminal.draw(|f| {
+        let size = f.size();
+        layout::draw_layout(f, size, state, file_browser, game, library);
+    }).unwrap();
+    buffer_text(terminal.backend().buffer())
+}
+
+fn buffer_text(buffer: &Buffer) -> String {
+    let width = buffer.area.width as usize;
+    buffer.content.chunks(width).map(|row| {
+        let line: String = row.iter().map(|cell| cell.symbol.as_str()).collect();
+        format!("{}\n", line.trim_end())
+    }).collect()
+}
+
+// Compare with the golden file, golden files are only written with UPDATE_SNAPSHOTS set
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let Ok(expected) = fs::read_to_string(&path) else {
+        panic!("Golden file {} is missing, run with UPDATE_SNAPSHOTS=1 to create it", path.display());
+    };
+    assert!(expected == actual, "Snapshot {} changed, expected:\n{}\nactual:\n{}", name, expected, actual);
+}
+
+fn game_with(headers: &[&str], cards: &[&[&str]]) -> Game {
+    let mut game = Game::new();
+    game.is_open = true;
+    game.start(Session::new(
+        headers.iter().map(|h| h.to_string()).collect(),
+        cards.iter().map(|card| card.iter().map(|c| c.to_string()).collect()).collect(),
+    ));
+    game
+}
+
+fn library_with_decks() -> Library {
+    let mut library = Library::new();
+    library.decks.entries = vec![String::from("/decks/french.csv"), String::from("/decks/broken.csv")];
+    library.summaries = vec![
+        DeckSummary { path: String::from("/decks/french.csv"), cards: 12, new: 4, due: 3, learned: 5, last_studied: Some(19000), ..DeckSummary::default() },
+        DeckSummary { path: String::from("/decks/broken.csv"), error: Some(String::from("missing header")), ..DeckSummary::default() },
+    ];
+    library
+}
+
+#[test]
+fn test_snapshot_simple_states() {
+    let file_browser = FileBrowser::new();
+    let game = Game::new();
+    let library = Library::new();
+
+    for (name, state) in [
+        ("welcome_empty", AppState::Welcome),
+        ("settings", AppState::Settings),
+        ("info", AppState::Info),
+        ("select_file", AppState::SelectFile),
+        ("exit", AppState::Exit),
+    ] {
+        assert_snapshot(name, &render(state, &file_browser, &game, &library));
+    }
+}
+
+#[test]
+fn test_snapshot_welcome_library() {
+    let mut library = library_with_decks();
+    // The last played deck is the one 'r' starts
+    let mut file_browser = FileBrowser::new();
+    file_browser.recent_decks.entries = vec![String::from("/decks/french.csv")];
+    assert_snapshot("welcome_library", &render(AppState::Welcome, &file_browser, &Game::new(), &library));
+
+    // Decks that describe themselves are listed by name, with the description of the highlighted one below
+    library.summaries[0].meta = DeckMeta {
+        name: Some(String::from("French basics")),
+        description: Some(String::from("Words for a first trip to Paris")),
+        author: Some(String::from("Jo")),
+        ..DeckMeta::default()
+    };
+    assert_snapshot("welcome_library_metadata", &render(AppState::Welcome, &file_browser, &Game::new(), &library));
+}
+
+#[test]
+fn test_snapshot_stats() {
+    // Empty dashboard for a fresh install
+    let mut library = Library::new();
+    library.dashboard = Dashboard::new(&[], &ProgressStore::default(), 0, 20_000);
+    assert_snapshot("stats_empty", &render(AppState::Stats, &FileBrowser::new(), &Game::new(), &library));
+
+    // A few weeks of reviews
+    let today = 20_000;
+    let mut reviews = Vec::new();
+    let mut store = ProgressStore::default();
+    for day in today - 40..=today {
+        for card in 0..day % 5 {
+            let correct = (day + card) % 3 != 0;
+            reviews.push(Review { deck: String::from("deck"), card: card.to_string(), day, correct, millis: 1000 + card * 700 });
+            store.record("deck", &card.to_string(), correct, day);
+        }
+    }
+    library.dashboard = Dashboard::new(&reviews, &store, 12, today);
+    assert_snapshot("stats", &render(AppState::Stats, &FileBrowser::new(), &Game::new(), &library));
+}
+
+#[test]
+fn test_snapshot_leeches() {
+    let mut library = Library::new();
+    assert_snapshot("leeches_empty", &render(AppState::Leeches, &FileBrowser::new(), &Game::new(), &library));
+
+    let leech = |card: &str, lapses, suspended| Leech { deck: String::from("/decks/french.csv"), card: card.to_string(), lapses, suspended };
+    library.leeches = vec![leech("La grenouille", 11, false), leech("Le chien", 8, true)];
+    library.selected_leech = 1;
+    assert_snapshot("leeches", &render(AppState::Leeches, &FileBrowser::new(), &Game::new(), &library));
+}
+
+#[test]
+fn test_snapshot_editor() {
+    let mut library = Library::new();
+    let mut editor = DeckEditor::new(
+        "/decks/french.csv",
+        vec![String::from("French"), String::from("English")],
+        vec![vec![String::from("La maison"), String::from("The house")], vec![String::from("Le chien"), String::from("The dog")]],
+        CsvStyle::default(),
+    );
+    editor.handle_keypress(KeyCode::Down);
+    editor.handle_keypress(KeyCode::Right);
+    library.editor = Some(editor);
+    assert_snapshot("editor", &render(AppState::Editor, &FileBrowser::new(), &Game::new(), &library));
+
+    // Typing into a cell
+    if let Some(ref mut editor) = library.editor {
+        editor.handle_keypress(KeyCode::Enter);
+        editor.handle_keypress(KeyCode::Char('s'));
+    }
+    assert_snapshot("editor_input", &render(AppState::Editor, &FileBrowser::new(), &Game::new(), &library));
+}
+
+#[test]
+fn test_snapshot_file_browser() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("french.csv"), "French;English\nLa maison;The house\nLe chien;The dog\n").unwrap();
+    fs::write(dir.path().join("notes.txt"), "").unwrap();
+    fs::create_dir(dir.path().join("more")).unwrap();
+
+    let mut file_browser = FileBrowser::new();
+    file_browser.current_directory = dir.path().to_str().unwrap().to_string();
+    file_browser.toggle();
+    let screen = |file_browser: &FileBrowser| render(AppState::FileBrowser, file_browser, &Game::new(), &Library::new());
+
+    // Directories first, the highlighted directory has no deck preview
+    assert_snapshot("file_browser", &screen(&file_browser));
+
+    // Highlighting the deck shows its preview
+    while file_browser.get_selected_file().map(String::as_str) != Some("french.csv") {
+        file_browser.handle_keypress(KeyCode::Down);
+    }
+    assert_snapshot("file_browser_preview", &screen(&file_browser));
+}
+
+#[test]
+fn test_snapshot_game() {
+    let library = Library::new();
+    let file_browser = FileBrowser::new();
+    let mut game = game_with(&["French", "English", "German"], &[&["La maison", "The house", "Das Haus"]]);
+
+    assert_snapshot("game_question", &render(AppState::Game, &file_browser, &game, &library));
+    game.next();
+    assert_snapshot("game_answer", &render(AppState::Game, &file_browser, &game, &library));
+    game.next();
+    game.next();
+    game.next();
+    assert_snapshot("game_grade_prompt", &render(AppState::Game, &file_browser, &game, &library));
+
+    // Fixing the card in the edit form
+    game.handle_keypress(KeyCode::Char('e'));
+    game.handle_keypress(KeyCode::Down);
+    assert_snapshot("game_edit_form", &render(AppState::Game, &file_browser, &game, &library));
+}
+
+#[test]
+fn test_snapshot_game_edge_cases() {
+    let library = Library::new();
+    let file_browser = FileBrowser::new();
+
+    // A deck with headers only and a deck that doesn't even have those
+    let game = game_with(&["French", "English"], &[]);
+    assert_snapshot("game_empty_deck", &render(AppState::Game, &file_browser, &game, &library));
+    let game = game_with(&[], &[]);
+    assert_snapshot("game_no_headers", &render(AppState::Game, &file_browser, &game, &library));
+
+    // The end of a session, with one card missed and known when it came back
+    let mut game = game_with(&["French", "English"], &[&["La maison", "The house"], &["Le chien", "The dog"]]);
+    for grade in ['n', 'y', 'y'] {
+        game.next();
+        game.next();
+        game.handle_keypress(KeyCode::Char(grade));
+    }
+    assert_snapshot("game_summary", &render(AppState::Game, &file_browser, &game, &library));
+
+    // The card list on top of the summary, and jumping to a card by its number
+    game.handle_keypress(KeyCode::Char('c'));
+    assert_snapshot("game_card_list", &render(AppState::Game, &file_browser, &game, &library));
+    game.handle_keypress(KeyCode::Enter);
+    game.handle_keypress(KeyCode::Char('g'));
+    game.handle_keypress(KeyCode::Char('2'));
+    assert_snapshot("game_jump", &render(AppState::Game, &file_browser, &game, &library));
+
+    // Tags show up under the question
+    let mut game = game_with(&["French", "English"], &[&["La maison", "The house"]]);
+    game.session.tags = vec![String::from("nouns, basics")];
+    assert_snapshot("game_question_tags", &render(AppState::Game, &file_browser, &game, &library));
+
+    // Cards of two decks studied together show their own headers and deck, grading them writes to a data dir of its own
+    let dir = tempfile::tempdir().unwrap();
+    let data_dir = dir.path().join("data");
+    fs::write(dir.path().join("fr.csv"), "French;English\nLa maison;The house\n").unwrap();
+    fs::write(dir.path().join("de.csv"), "German;English\nDas Haus;The house\n").unwrap();
+    let decks = [dir.path().join("fr.csv"), dir.path().join("de.csv")].map(|deck| deck.to_string_lossy().to_string());
+    let mut game = Game::new();
+    let session = Session::load_decks(&data_dir, &decks, 5, None, ShuffleMode::None, &mut game.rng).unwrap();
+    game.start(session);
+    game.next();
+    game.next();
+    game.handle_keypress(KeyCode::Char('y'));
+    assert!(data_dir.join("progress.csv").exists());
+    assert_snapshot("game_several_decks", &render(AppState::Game, &file_browser, &game, &library));
+
+    // A deck with a name and the languages of its columns
+    fs::write(dir.path().join("named.csv"), "#name: French basics\n#language.French: fr\nFrench;English\nLa maison;The house\n").unwrap();
+    let mut game = Game::new();
+    let session = Session::load(&data_dir, &dir.path().join("named.csv").to_string_lossy(), 5, None, None, ShuffleMode::None, &mut game.rng).unwrap();
+    game.start(session);
+    assert_snapshot("game_deck_metadata", &render(AppState::Game, &file_browser, &game, &library));
+
+    // Markdown cards show their emphasis and code blocks
+    fs::write(dir.path().join("rust.md"), "## What does this print?\n\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\n## What is Rust?\n\nA **systems** language\n").unwrap();
+    let mut game = Game::new();
+    let session = Session::load(&data_dir, &dir.path().join("rust.md").to_string_lossy(), 5, None, None, ShuffleMode::None, &mut game.rng).unwrap();
+    game.start(session);
+    game.next();
+    assert_snapshot("game_markdown", &render(AppState::Game, &file_browser, &game, &library));
+
+    // A deck with only a question column
+    let game = game_with(&["Word"], &[&["maison"]]);
+    assert_snapshot("game_one_column", &render(AppState::Game, &file_browser, &game, &library));
+}
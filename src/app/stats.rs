@@ -0,0 +1,105 @@
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    symbols,
+    text::{Span, Spans},
+    widgets::{Axis, BarChart, Block, Borders, Chart, Dataset, GraphType, Paragraph, Sparkline},
+    Frame,
+};
+use trashcards::stats::{self, Dashboard, RECENT_DAYS};
+
+// Weekday labels of the heatmap rows, only every other one to keep it readable
+const WEEKDAYS: [&str; 7] = ["Mo", "  ", "We", "  ", "Fr", "  ", "Su"];
+
+// Function to draw the stats dashboard as the main content
+pub fn draw<B: Backend>(f: &mut Frame<B>, size: Rect, dashboard: &Dashboard) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(9), Constraint::Length(7), Constraint::Min(4)].as_ref())
+        .split(size);
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(2 * stats::HEATMAP_WEEKS as u16 + 5), Constraint::Min(10)].as_ref())
+        .split(rows[0]);
+    let middle = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(rows[1]);
+
+    draw_heatmap(f, top[0], dashboard);
+    draw_retention(f, top[1], dashboard);
+
+    let maturity = &dashboard.maturity;
+    let bars = [("New", maturity.new), ("Learn", maturity.learning), ("Young", maturity.young), ("Mature", maturity.mature)];
+    let maturity_chart = BarChart::default()
+        .block(Block::default().borders(Borders::ALL).title("Cards by maturity"))
+        .data(&bars)
+        .bar_width(6)
+        .bar_style(Style::default().fg(Color::Green))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Green));
+    f.render_widget(maturity_chart, middle[0]);
+
+    let average = dashboard.average_answer_millis
+        .map(|millis| format!("{:.1}s", millis as f64 / 1000.0))
+        .unwrap_or_else(|| String::from("-"));
+    let answer_times = Sparkline::default()
+        .block(Block::default().borders(Borders::ALL).title(format!("Answer time, {} on average", average)))
+        .data(&dashboard.answer_millis_per_day)
+        .style(Style::default().fg(Color::Yellow));
+    f.render_widget(answer_times, middle[1]);
+
+    let total: u64 = dashboard.forecast.iter().sum();
+    let forecast = Sparkline::default()
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Due in the next {} days: {} today, {} in total",
+            RECENT_DAYS,
+            dashboard.forecast.first().copied().unwrap_or(0),
+            total,
+        )))
+        .data(&dashboard.forecast)
+        .style(Style::default().fg(Color::Cyan));
+    f.render_widget(forecast, rows[2]);
+}
+
+// Calendar of the last weeks, one column per week and one row per weekday, darker means more reviews
+fn draw_heatmap<B: Backend>(f: &mut Frame<B>, area: Rect, dashboard: &Dashboard) {
+    let max = dashboard.reviews_per_day.iter().copied().max().unwrap_or(0);
+    let lines: Vec<Spans> = WEEKDAYS.iter().enumerate().map(|(weekday, label)| {
+        let mut spans = vec![Span::raw(format!("{} ", label))];
+        for week in 0..stats::HEATMAP_WEEKS as usize {
+            let cell = match dashboard.reviews_per_day.get(week * 7 + weekday) {
+                None => Span::raw("  "), // Still to come this week
+                Some(0) => Span::styled("· ", Style::default().fg(Color::DarkGray)),
+                Some(&count) => {
+                    let symbol = ["░", "▒", "▓", "█"][((count * 4 - 1) / max.max(1)).min(3) as usize];
+                    Span::styled(format!("{} ", symbol), Style::default().fg(Color::Green))
+                }
+            };
+            spans.push(cell);
+        }
+        Spans::from(spans)
+    }).collect();
+
+    let title = format!("Reviews ({} total)", dashboard.total_reviews);
+    f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+}
+
+// Share of correct answers on each of the last days
+fn draw_retention<B: Backend>(f: &mut Frame<B>, area: Rect, dashboard: &Dashboard) {
+    let title = match dashboard.overall_retention {
+        Some(retention) => format!("Retention {:.0}%", retention),
+        None => String::from("Retention"),
+    };
+    let datasets = vec![Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(Color::Magenta))
+        .data(&dashboard.retention)];
+    let first_day = -((RECENT_DAYS - 1) as f64);
+    let chart = Chart::new(datasets)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .x_axis(Axis::default().bounds([first_day, 0.0]).labels(vec![Span::raw(format!("{}d", first_day)), Span::raw("today")]))
+        .y_axis(Axis::default().bounds([0.0, 100.0]).labels(vec![Span::raw("0%"), Span::raw("100%")]));
+    f.render_widget(chart, area);
+}
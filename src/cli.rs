@@ -0,0 +1,371 @@
+use clap::{Args, Parser, Subcommand};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use trashcards::file_handler;
+use trashcards::library::{self, DeckSummary};
+use trashcards::lint::{self, Severity};
+use trashcards::progress::{self, ProgressStore};
+use trashcards::query::Query;
+use trashcards::session::ShuffleMode;
+use trashcards::tags::TagFilter;
+
+#[derive(Parser)]
+#[command(name = "trashcards", version, about = "Terminal flashcards", args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub options: AppOptions,
+}
+
+impl Cli {
+    // The options given to the app or to the study session it starts with
+    pub fn options(&self) -> AppOptions {
+        match &self.command {
+            Some(Command::Study { options, .. }) | Some(Command::Custom { options, .. }) => options.clone(),
+            _ => self.options.clone(),
+        }
+    }
+}
+
+// Options for studying, the tools don't take them and --plain can't record or replay
+#[derive(Args, Clone, Default)]
+pub struct AppOptions {
+    /// Seed for drawing cards, the same seed gives the same session
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Card order before a session takes its cards: none, full or buckets (hardest first)
+    #[arg(long, value_parser = ShuffleMode::from_str)]
+    pub shuffle: Option<ShuffleMode>,
+    /// Write every key press and mouse click of the app to a file
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+    /// Play back a recording made with --record before taking live input
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Open the app right in a game with the given deck, several decks are studied together
+    Study {
+        #[arg(required = true)]
+        decks: Vec<PathBuf>,
+        /// Number of cards in the session
+        #[arg(long, default_value_t = crate::app::game::DEFAULT_SESSION_SIZE)]
+        size: usize,
+        /// Question column and answer columns, counted from 0, e.g. "2:0" or "0:1,2"
+        #[arg(long, value_parser = parse_direction)]
+        direction: Option<Direction>,
+        /// Only study cards whose tags match, e.g. "networking and not basics"
+        #[arg(long, value_parser = TagFilter::from_str)]
+        tags: Option<TagFilter>,
+        /// Ask line by line on the normal terminal instead of opening the full-screen app
+        #[arg(long, conflicts_with_all = ["record", "replay"])]
+        plain: bool,
+        #[command(flatten)]
+        options: AppOptions,
+    },
+    /// Study the cards of several decks that match a query, e.g. "due:today lapses>3"
+    Custom {
+        #[arg(value_parser = Query::from_str)]
+        query: Query,
+        /// Decks or directories of decks to search, the whole library if none are given
+        decks: Vec<PathBuf>,
+        /// Number of cards in the session
+        #[arg(long, default_value_t = crate::app::game::DEFAULT_SESSION_SIZE)]
+        size: usize,
+        /// Ask line by line on the normal terminal instead of opening the full-screen app
+        #[arg(long, conflicts_with_all = ["record", "replay"])]
+        plain: bool,
+        #[command(flatten)]
+        options: AppOptions,
+    },
+    #[command(flatten)]
+    Tool(Tool),
+}
+
+// Commands that run without the app and exit
+#[derive(Subcommand)]
+pub enum Tool {
+    /// Check decks for problems
+    Lint {
+        #[arg(required = true)]
+        decks: Vec<PathBuf>,
+    },
+    /// Convert a CSV file with any delimiter into a deck (or the other way around)
+    Convert {
+        input: PathBuf,
+        output: PathBuf,
+        /// Delimiter of the input, defaults to tab for .tsv files and ';' otherwise
+        #[arg(long, value_parser = parse_delimiter)]
+        input_delimiter: Option<u8>,
+        /// Delimiter of the output
+        #[arg(long, value_parser = parse_delimiter, default_value = ";")]
+        delimiter: u8,
+    },
+    /// Print card counts of decks, or of the whole library
+    Stats {
+        decks: Vec<PathBuf>,
+    },
+    /// Add decks, or every deck inside a directory, to the library
+    Import {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+}
+
+// Column order of a study session, the question column comes first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Direction(pub Vec<usize>);
+
+// "2:0" -> [2, 0], "0:1,2" -> [0, 1, 2]
+pub fn parse_direction(value: &str) -> Result<Direction, String> {
+    file_handler::parse_direction(value).map(Direction)
+}
+
+// Single character delimiters, "tab" or "\t" for tabs
+fn parse_delimiter(value: &str) -> Result<u8, String> {
+    match value {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        _ if value.len() == 1 => Ok(value.as_bytes()[0]),
+        _ => Err(format!("{:?} is not a single character", value)),
+    }
+}
+
+// Run a non-interactive command, returns false when the command found problems
+pub fn run(tool: Tool, data_dir: &Path) -> Result<bool, Box<dyn Error>> {
+    match tool {
+        Tool::Lint { decks } => Ok(lint(&decks)),
+        Tool::Convert { input, output, input_delimiter, delimiter } => {
+            convert(&input, &output, input_delimiter, delimiter)?;
+            Ok(true)
+        }
+        Tool::Stats { decks } => {
+            stats(&decks, data_dir);
+            Ok(true)
+        }
+        Tool::Import { paths } => import(&paths, data_dir),
+    }
+}
+
+fn lint(decks: &[PathBuf]) -> bool {
+    let mut clean = true;
+    for deck in decks {
+        for issue in lint::lint_deck(deck) {
+            println!("{}:{}", deck.display(), issue);
+            if issue.severity == Severity::Error {
+                clean = false;
+            }
+        }
+    }
+    clean
+}
+
+fn convert(input: &Path, output: &Path, input_delimiter: Option<u8>, delimiter: u8) -> Result<(), Box<dyn Error>> {
+    // The output is always CSV, a .json or .md file with CSV in it couldn't be loaded again
+    if file_handler::is_structured_deck(output) {
+        return Err(format!("{} is not a CSV file, convert only writes CSV decks", output.display()).into());
+    }
+    let input_delimiter = input_delimiter.unwrap_or_else(|| {
+        match input.extension().and_then(|e| e.to_str()) {
+            Some("tsv") => b'\t',
+            _ => file_handler::DELIMITER,
+        }
+    });
+    let input = input.to_str().ok_or("input path is not valid UTF-8")?;
+    // JSON, TOML and YAML decks turn into CSV the same way
+    let (headers, rows) = match file_handler::is_structured_deck(Path::new(input)) {
+        true => file_handler::read_csv_with_headers(input)?,
+        false => file_handler::read_csv_with_delimiter(input, input_delimiter)?,
+    };
+    file_handler::write_csv_with_delimiter(output, &headers, &rows, delimiter)?;
+    println!("Converted {} cards to {}", rows.len(), output.display());
+    Ok(())
+}
+
+// Summaries of the given decks, or of every deck in the library
+pub fn deck_summaries(decks: &[PathBuf], data_dir: &Path) -> Vec<DeckSummary> {
+    let store = ProgressStore::load(data_dir);
+    let today = progress::today();
+    deck_keys(decks, data_dir).iter().map(|deck| DeckSummary::load(deck, &store, today)).collect()
+}
+
+// Keys of the given decks, or of every deck in the library if there are none
+pub fn deck_keys(decks: &[PathBuf], data_dir: &Path) -> Vec<String> {
+    if decks.is_empty() {
+        library::registered_decks(data_dir).entries
+    } else {
+        decks.iter().map(|deck| progress::deck_key(deck)).collect()
+    }
+}
+
+fn stats(decks: &[PathBuf], data_dir: &Path) {
+    println!("{:<40} {:>6} {:>6} {:>6} {:>8}  Last studied", "Deck", "Cards", "New", "Due", "Learned");
+    for summary in deck_summaries(decks, data_dir) {
+        match summary.error {
+            Some(err) => println!("{:<40} unreadable: {}", summary.path, err),
+            None => println!(
+                "{:<40} {:>6} {:>6} {:>6} {:>8}  {}",
+                summary.path,
+                summary.cards,
+                summary.new,
+                summary.due,
+                summary.learned,
+                summary.last_studied.map(progress::format_day).unwrap_or_else(|| String::from("never")),
+            ),
+        }
+    }
+}
+
+// Decks found at the given paths, directories are searched one level deep
+pub fn find_decks(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut decks = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut found: Vec<PathBuf> = fs::read_dir(path)
+                .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+                .unwrap_or_default();
+            found.retain(|p| p.is_file() && file_handler::is_supported_deck(p));
+            found.sort();
+            decks.extend(found);
+        } else {
+            decks.push(path.clone());
+        }
+    }
+    decks
+}
+
+fn import(paths: &[PathBuf], data_dir: &Path) -> Result<bool, Box<dyn Error>> {
+    let mut decks = library::registered_decks(data_dir);
+
+    let mut all_imported = true;
+    for deck in find_decks(paths) {
+        if !deck.is_file() || !file_handler::is_supported_deck(&deck) {
+            eprintln!("Skipped {}: not a supported deck", deck.display());
+            all_imported = false;
+            continue;
+        }
+        decks.push_front(&progress::deck_key(&deck));
+        println!("Imported {}", deck.display());
+    }
+    decks.save()?;
+    Ok(all_imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_direction() {
+        assert_eq!(parse_direction("2:0"), Ok(Direction(vec![2, 0])));
+        assert_eq!(parse_direction("0:1,2"), Ok(Direction(vec![0, 1, 2])));
+        assert!(parse_direction("2").is_err());
+        assert!(parse_direction("a:0").is_err());
+    }
+
+    #[test]
+    fn test_parse_study_arguments() {
+        let cli = Cli::try_parse_from(["trashcards", "study", "deck.csv", "--size", "20", "--direction", "2:0"]).unwrap();
+        match cli.command {
+            Some(Command::Study { decks, size, direction, tags, plain, .. }) => {
+                assert!(!plain);
+                assert!(tags.is_none());
+                assert_eq!(decks, vec![PathBuf::from("deck.csv")]);
+                assert_eq!(size, 20);
+                assert_eq!(direction, Some(Direction(vec![2, 0])));
+            }
+            _ => panic!("expected the study command"),
+        }
+
+        // No arguments just opens the app
+        assert!(Cli::try_parse_from(["trashcards"]).unwrap().command.is_none());
+
+        // Recording works for the app and for a study session, but not line by line or with the tools
+        let cli = Cli::try_parse_from(["trashcards", "study", "deck.csv", "--record", "bug.txt"]).unwrap();
+        assert_eq!(cli.options().record, Some(PathBuf::from("bug.txt")));
+        assert!(cli.options().replay.is_none());
+        let cli = Cli::try_parse_from(["trashcards", "--replay", "bug.txt"]).unwrap();
+        assert_eq!(cli.options().replay, Some(PathBuf::from("bug.txt")));
+        assert!(Cli::try_parse_from(["trashcards", "study", "deck.csv", "--plain", "--record", "bug.txt"]).is_err());
+        assert!(Cli::try_parse_from(["trashcards", "custom", "due:today", "--plain", "--replay", "bug.txt"]).is_err());
+        assert!(Cli::try_parse_from(["trashcards", "--record", "bug.txt", "study", "deck.csv"]).is_err());
+        assert!(Cli::try_parse_from(["trashcards", "lint", "deck.csv", "--record", "bug.txt"]).is_err());
+        assert!(Cli::try_parse_from(["trashcards", "--replay", "bug.txt", "stats"]).is_err());
+
+        let cli = Cli::try_parse_from(["trashcards", "study", "deck.csv", "--seed", "7", "--shuffle", "buckets"]).unwrap();
+        assert_eq!(cli.options().seed, Some(7));
+        assert_eq!(cli.options().shuffle, Some(ShuffleMode::Buckets));
+        assert!(Cli::try_parse_from(["trashcards", "--shuffle", "sideways"]).is_err());
+        assert!(Cli::try_parse_from(["trashcards", "stats", "--seed", "7"]).is_err());
+
+        let cli = Cli::try_parse_from(["trashcards", "study", "deck.csv", "--tags", "networking and not basics"]).unwrap();
+        match cli.command {
+            Some(Command::Study { tags, .. }) => assert_eq!(tags.unwrap().to_string(), "networking and not basics"),
+            _ => panic!("expected the study command"),
+        }
+        assert!(Cli::try_parse_from(["trashcards", "study", "deck.csv", "--tags", "networking and"]).is_err());
+        assert!(Cli::try_parse_from(["trashcards", "study"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_custom_arguments() {
+        let cli = Cli::try_parse_from(["trashcards", "custom", "due:today lapses>3", "decks/", "--size", "30"]).unwrap();
+        match cli.command {
+            Some(Command::Custom { query, decks, size, plain, .. }) => {
+                assert_eq!(query.to_string(), "due:today lapses>3");
+                assert_eq!(decks, vec![PathBuf::from("decks/")]);
+                assert_eq!(size, 30);
+                assert!(!plain);
+            }
+            _ => panic!("expected the custom command"),
+        }
+        assert!(Cli::try_parse_from(["trashcards", "custom", "due:someday"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_tool_arguments() {
+        let cli = Cli::try_parse_from(["trashcards", "lint", "a.csv", "b.csv"]).unwrap();
+        match cli.command {
+            Some(Command::Tool(Tool::Lint { decks })) => assert_eq!(decks, vec![PathBuf::from("a.csv"), PathBuf::from("b.csv")]),
+            _ => panic!("expected the lint command"),
+        }
+        assert!(matches!(Cli::try_parse_from(["trashcards", "stats"]).unwrap().command, Some(Command::Tool(Tool::Stats { .. }))));
+    }
+
+    #[test]
+    fn test_convert_tsv() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("deck.tsv");
+        let output = dir.path().join("deck.csv");
+        fs::write(&input, "Question\tAnswer\nWhat is Rust?\tA language; a fast one\n").unwrap();
+
+        convert(&input, &output, None, b';').unwrap();
+        let (headers, rows) = file_handler::read_csv_with_headers(output.to_str().unwrap()).unwrap();
+        assert_eq!(headers, vec!["Question", "Answer"]);
+        assert_eq!(rows[0], vec!["What is Rust?", "A language; a fast one"]);
+
+        // Other formats can't be written
+        let json = dir.path().join("deck.json");
+        assert!(convert(&input, &json, None, b';').is_err());
+        assert!(!json.exists());
+    }
+
+    #[test]
+    fn test_import_directory() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let decks_dir = tempfile::tempdir().unwrap();
+        fs::write(decks_dir.path().join("a.csv"), "Question;Answer\n").unwrap();
+        fs::write(decks_dir.path().join("b.csv"), "Question;Answer\n").unwrap();
+        fs::write(decks_dir.path().join("huhcat.gif"), "").unwrap();
+
+        assert!(import(&[decks_dir.path().to_path_buf()], data_dir.path()).unwrap());
+
+        let summaries = deck_summaries(&[], data_dir.path());
+        assert_eq!(summaries.len(), 2);
+        assert!(summaries.iter().all(|s| s.error.is_none()));
+    }
+}
@@ -0,0 +1,89 @@
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::scheduler::LEECH_LAPSES;
+use crate::session::{ShuffleMode, RETRY_AFTER};
+
+// Settings read from config.toml in the data dir, e.g.
+//
+//   seed = 1234        # same cards in the same order every time
+//   shuffle = "buckets"
+//   retry_after = 5    # a missed card comes back after 5 other cards, 0 turns that off
+//   leech_lapses = 6   # forgetting a card 6 times makes it a leech, 0 turns that off
+//   suspend_leeches = true
+//
+// Only flat `key = value` lines are understood. Command line options win over the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub seed: Option<u64>,     // Seed for drawing cards, a random one when unset
+    pub shuffle: ShuffleMode, // How decks are ordered before a session takes its cards
+    pub retry_after: usize,   // Cards between a missed card and its next try in the same session
+    pub leech_lapses: u32,    // Lapses that make a card a leech
+    pub suspend_leeches: bool, // Leave leeches out of sessions until they are rewritten
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { seed: None, shuffle: ShuffleMode::default(), retry_after: RETRY_AFTER, leech_lapses: LEECH_LAPSES, suspend_leeches: false }
+    }
+}
+
+impl Config {
+    // A missing file just means the defaults
+    pub fn load(data_dir: &Path) -> Result<Self, Box<dyn Error>> {
+        match fs::read_to_string(config_path(data_dir)) {
+            Ok(text) => Self::parse(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut config = Config::default();
+        for (number, line) in text.lines().enumerate() {
+            // Drop comments, '#' inside a quoted value isn't worth supporting yet
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| format!("config line {}: expected key = value", number + 1))?;
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "seed" => config.seed = Some(value.parse().map_err(|_| format!("config line {}: {:?} is not a seed", number + 1, value))?),
+                "retry_after" => config.retry_after = value.parse().map_err(|_| format!("config line {}: {:?} is not a number of cards", number + 1, value))?,
+                "leech_lapses" => config.leech_lapses = value.parse().map_err(|_| format!("config line {}: {:?} is not a number of lapses", number + 1, value))?,
+                "suspend_leeches" => config.suspend_leeches = value.parse().map_err(|_| format!("config line {}: {:?} is not true or false", number + 1, value))?,
+                "shuffle" => config.shuffle = value.parse().map_err(|err| format!("config line {}: {}", number + 1, err))?,
+                other => return Err(format!("config line {}: unknown setting {:?}", number + 1, other).into()),
+            }
+        }
+        Ok(config)
+    }
+}
+
+pub fn config_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("config.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse("# my settings\nseed = 42\nshuffle = \"none\" # keep the file order\n").unwrap();
+        assert_eq!(config, Config { seed: Some(42), shuffle: ShuffleMode::None, ..Config::default() });
+        assert_eq!(Config::parse("retry_after = 0").unwrap().retry_after, 0);
+        let config = Config::parse("leech_lapses = 4\nsuspend_leeches = true").unwrap();
+        assert_eq!((config.leech_lapses, config.suspend_leeches), (4, true));
+        assert!(Config::parse("suspend_leeches = maybe").is_err());
+
+        assert!(Config::parse("seed = soon").is_err());
+        assert!(Config::parse("colour = blue").is_err());
+
+        // No file, no settings
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Config::load(dir.path()).unwrap(), Config::default());
+    }
+}
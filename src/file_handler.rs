@@ -0,0 +1,622 @@
+use csv::{QuoteStyle, ReaderBuilder, StringRecord, Terminator, WriterBuilder};
+use serde_json::Value;
+use crate::markdown;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+// Headers and rows of a loaded deck
+pub type CsvData = (Vec<String>, Vec<Vec<String>>);
+
+// File extensions the loader knows how to read as a deck
+pub const SUPPORTED_EXTENSIONS: [&str; 7] = ["csv", "json", "toml", "yaml", "yml", "md", "markdown"];
+
+// Decks that aren't CSV files: a list of cards with named fields (see read_structured_deck) or Markdown notes
+const STRUCTURED_EXTENSIONS: [&str; 6] = ["json", "toml", "yaml", "yml", "md", "markdown"];
+
+fn extension(path: &Path) -> String {
+    path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
+
+// Check by extension if a file can be loaded as a deck. french.csv.toml is the metadata of french.csv, not a deck.
+pub fn is_supported_deck(path: &Path) -> bool {
+    let sidecar = extension(path) == "toml" && path.file_stem().map(|stem| is_supported_deck(Path::new(stem))).unwrap_or(false);
+    SUPPORTED_EXTENSIONS.contains(&extension(path).as_str()) && !sidecar
+}
+
+pub fn is_structured_deck(path: &Path) -> bool {
+    STRUCTURED_EXTENSIONS.contains(&extension(path).as_str())
+}
+
+// Cards of Markdown decks keep their emphasis and code blocks for the game to show
+pub fn is_markdown_deck(path: &Path) -> bool {
+    matches!(extension(path).as_str(), "md" | "markdown")
+}
+
+// Delimiter of new deck files, and of the ones where no other delimiter stands out
+pub const DELIMITER: u8 = b';';
+
+// Read the headers and every row of a deck, in file order. JSON, TOML, YAML and Markdown decks come out the same way,
+// CSV decks are read with the delimiter their header line uses.
+pub fn read_csv_with_headers(file_path: &str) -> Result<CsvData, Box<dyn Error>> {
+    if is_structured_deck(Path::new(file_path)) {
+        return Ok(read_structured_deck(file_path)?.0);
+    }
+    let style = detect_style(split_metadata(&fs::read_to_string(file_path)?).1);
+    read_csv_with_delimiter(file_path, style.delimiter)
+}
+
+// Read the headers and every row of a CSV file with any delimiter, e.g. to convert it into a deck
+pub fn read_csv_with_delimiter(file_path: &str, delimiter: u8) -> Result<CsvData, Box<dyn Error>> {
+    // Read the CSV file, the metadata lines at the top aren't part of it
+    let text = fs::read_to_string(file_path)?;
+    let (_, csv) = split_metadata(&text);
+
+    // Create a CSV reader with the given delimiter
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(true) // Specify that the CSV has headers
+        .delimiter(delimiter)
+        .from_reader(csv.as_bytes());
+
+    // Read headers and convert to Vec<String>
+    let headers = rdr
+        .headers()? // Retrieve headers
+        .iter()
+        .map(|header| header.to_string())
+        .collect::<Vec<String>>();
+
+
+    let mut records = Vec::new(); // Vec to hold the rows
+
+    // Iterate through each row in the CSV and store it
+    for result in rdr.records() {
+        let record: StringRecord = result?; // Get the record for the current row
+        let row = record.iter().map(|field| field.to_string()).collect::<Vec<String>>(); // Convert to Vec<String>
+        records.push(row); // Add row to the records vector
+    }
+
+    Ok((headers, records))
+}
+
+// Write headers and rows to a deck file with the same delimiter the loader expects
+pub fn write_csv_with_headers(file_path: &Path, headers: &[String], rows: &[Vec<String>]) -> Result<(), Box<dyn Error>> {
+    write_csv_with_delimiter(file_path, headers, rows, DELIMITER)
+}
+
+pub fn write_csv_with_delimiter(file_path: &Path, headers: &[String], rows: &[Vec<String>], delimiter: u8) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true) // Rows don't have to fill every column
+        .from_path(file_path)?;
+
+    wtr.write_record(headers)?;
+    for row in rows {
+        wtr.write_record(row)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+// How a deck file is written, so saving an edited deck doesn't reformat the whole file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvStyle {
+    pub delimiter: u8,
+    pub quote_all: bool, // Every field in quotes instead of only the ones that need it
+    pub crlf: bool,      // Windows line endings
+}
+
+impl Default for CsvStyle {
+    fn default() -> Self {
+        CsvStyle { delimiter: DELIMITER, quote_all: false, crlf: false }
+    }
+}
+
+// Guess the style from the text of a file: the most common delimiter in the header line,
+// and quotes only count as the style if the first lines start and end with them
+pub fn detect_style(text: &str) -> CsvStyle {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty()).take(2).peekable();
+    let Some(header) = lines.peek().copied() else {
+        return CsvStyle::default();
+    };
+    let count = |delimiter: u8| header.bytes().filter(|b| *b == delimiter).count();
+    let delimiter = [DELIMITER, b',', b'\t'].into_iter().max_by_key(|d| (count(*d), *d == DELIMITER)).unwrap_or(DELIMITER);
+
+    let quoted = |line: &str| line.trim().len() > 1 && line.trim().starts_with('"') && line.trim().ends_with('"');
+    CsvStyle {
+        delimiter,
+        quote_all: lines.all(quoted),
+        crlf: text.contains("\r\n"),
+    }
+}
+
+// Read a deck together with its style, for editing it and writing it back
+pub fn read_deck_with_style(file_path: &str) -> Result<(CsvData, CsvStyle), Box<dyn Error>> {
+    // Writing one back as CSV would throw away its format
+    if is_structured_deck(Path::new(file_path)) {
+        return Err("Only CSV decks can be edited in trashcards, this one has to be changed in its file".into());
+    }
+    let style = detect_style(split_metadata(&fs::read_to_string(file_path)?).1);
+    Ok((read_csv_with_delimiter(file_path, style.delimiter)?, style))
+}
+
+// Metadata lines at the top of the file are kept, so editing a deck doesn't lose them.
+// The deck is written to a temp file next to it first and then moved over it, so a failed write leaves the old one.
+pub fn write_csv_with_style(file_path: &Path, headers: &[String], rows: &[Vec<String>], style: CsvStyle) -> Result<(), Box<dyn Error>> {
+    let metadata = match fs::read_to_string(file_path) {
+        Ok(text) => split_metadata(&text).0.to_string(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into()),
+    };
+    let dir = file_path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut file = NamedTempFile::new_in(dir)?;
+    file.write_all(metadata.as_bytes())?;
+
+    let mut wtr = WriterBuilder::new()
+        .delimiter(style.delimiter)
+        .quote_style(if style.quote_all { QuoteStyle::Always } else { QuoteStyle::Necessary })
+        .terminator(if style.crlf { Terminator::CRLF } else { Terminator::Any(b'\n') })
+        .flexible(true)
+        .from_writer(&mut file);
+
+    wtr.write_record(headers)?;
+    for row in rows {
+        wtr.write_record(row)?;
+    }
+    wtr.flush()?;
+    drop(wtr);
+    file.persist(file_path)?;
+    Ok(())
+}
+
+// Rewrite one card of a deck file in the file's own style. `columns` maps the given fields to file columns,
+// the card is found by its old fields.
+pub fn replace_row(file_path: &str, columns: &[usize], old: &[String], new: &[String]) -> Result<(), Box<dyn Error>> {
+    let ((headers, mut rows), style) = read_deck_with_style(file_path)?;
+    let matches = |row: &Vec<String>| columns.iter().zip(old).all(|(&c, field)| row.get(c).map(|f| f == field).unwrap_or(field.is_empty()));
+    let row = rows.iter_mut().find(|row| matches(row)).ok_or("The card isn't in the deck file anymore")?;
+    for (&c, field) in columns.iter().zip(new) {
+        if row.len() <= c {
+            row.resize(c + 1, String::new());
+        }
+        row[c] = field.clone();
+    }
+    write_csv_with_style(Path::new(file_path), &headers, &rows, style)
+}
+
+// "2:0" -> [2, 0], "0:1,2" -> [0, 1, 2]: the question column, then the answer columns
+pub fn parse_direction(value: &str) -> Result<Vec<usize>, String> {
+    let (question, answers) = value.split_once(':').ok_or("expected QUESTION:ANSWER[,ANSWER...]")?;
+    let parse = |s: &str| s.trim().parse::<usize>().map_err(|_| format!("{:?} is not a column number", s));
+
+    let mut columns = vec![parse(question)?];
+    for answer in answers.split(',') {
+        columns.push(parse(answer)?);
+    }
+    Ok(columns)
+}
+
+// Reorder the columns of a deck: the first index is the question column, the rest are the answers in order
+pub fn apply_direction(headers: Vec<String>, rows: Vec<Vec<String>>, columns: &[usize]) -> Result<CsvData, Box<dyn Error>> {
+    if columns.len() < 2 {
+        return Err("A direction needs a question column and at least one answer column".into());
+    }
+    if let Some(column) = columns.iter().find(|&&c| c >= headers.len()) {
+        return Err(format!("Column {} doesn't exist, the deck has {} columns", column, headers.len()).into());
+    }
+
+    let pick = |fields: &Vec<String>| columns.iter().map(|&c| fields.get(c).cloned().unwrap_or_default()).collect::<Vec<String>>();
+    let rows = rows.iter().map(pick).collect();
+    Ok((pick(&headers), rows))
+}
+
+// What a deck says about itself. CSV has no place for it, so it goes either in `#key: value` lines
+// at the top of the deck, before the header line:
+//
+//   #name: French basics
+//   #author: Jo
+//   #direction: 1:0          # ask English, answer French unless --direction says otherwise
+//   #language.French: fr
+//   #retry_after: 5
+//   French;English
+//
+// or in `key = value` lines of a sidecar file next to it, french.csv -> french.csv.toml. The sidecar
+// wins when both set something. `#` lines that don't start with one of the keys and ':' are plain comments,
+// like `# Source: https://...`, while the sidecar only takes known keys.
+// JSON, TOML and YAML decks set the same keys next to their cards. The settings of a deck win over the ones from config.toml.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeckMeta {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub direction: Option<Vec<usize>>,   // Columns the deck is studied in unless the command line picks others
+    pub languages: Vec<(String, String)>, // Language of each column by column name
+    pub retry_after: Option<usize>,
+    pub leech_lapses: Option<u32>,
+    pub suspend_leeches: Option<bool>,
+}
+
+impl DeckMeta {
+    fn is_setting(key: &str) -> bool {
+        matches!(key, "name" | "description" | "author" | "direction" | "retry_after" | "leech_lapses" | "suspend_leeches") || key.starts_with("language.")
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "name" => self.name = Some(value.to_string()),
+            "description" => self.description = Some(value.to_string()),
+            "author" => self.author = Some(value.to_string()),
+            "direction" => self.direction = Some(parse_direction(value)?),
+            "retry_after" => self.retry_after = Some(value.parse().map_err(|_| format!("{:?} is not a number of cards", value))?),
+            "leech_lapses" => self.leech_lapses = Some(value.parse().map_err(|_| format!("{:?} is not a number of lapses", value))?),
+            "suspend_leeches" => self.suspend_leeches = Some(value.parse().map_err(|_| format!("{:?} is not true or false", value))?),
+            _ => {
+                let Some(column) = key.strip_prefix("language.") else {
+                    return Err(format!("unknown deck setting {:?}", key));
+                };
+                self.languages.retain(|(c, _)| c != column);
+                self.languages.push((column.to_string(), value.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    // Language of a column, e.g. "fr" for French
+    pub fn language(&self, column: &str) -> Option<&str> {
+        self.languages.iter().find(|(c, _)| c.trim() == column.trim()).map(|(_, language)| language.as_str())
+    }
+}
+
+// Sidecar file with the metadata of a deck
+pub fn metadata_path(deck: &Path) -> PathBuf {
+    let mut name = deck.file_name().unwrap_or_default().to_os_string();
+    name.push(".toml");
+    deck.with_file_name(name)
+}
+
+// Split the `#` lines at the top of a deck from the CSV part
+pub fn split_metadata(text: &str) -> (&str, &str) {
+    let mut end = 0;
+    while text[end..].starts_with('#') {
+        end = text[end..].find('\n').map(|i| end + i + 1).unwrap_or(text.len());
+    }
+    text.split_at(end)
+}
+
+// Read the metadata of a deck from its header lines, or the settings of a JSON, TOML, YAML or Markdown deck,
+// and its sidecar file. A deck without any has the default one.
+pub fn read_metadata(file_path: &str) -> Result<DeckMeta, Box<dyn Error>> {
+    let (mut meta, text) = match is_structured_deck(Path::new(file_path)) {
+        true => (read_structured_deck(file_path)?.1, String::new()),
+        false => (DeckMeta::default(), fs::read_to_string(file_path)?),
+    };
+    for (number, line) in split_metadata(&text).0.lines().enumerate() {
+        let line = line.trim_start_matches('#');
+        let Some((key, value)) = line.split_once(':').filter(|(key, _)| DeckMeta::is_setting(key.trim())) else {
+            continue;
+        };
+        // Comments after the value, like in the sidecar
+        let value = value.split(" #").next().unwrap_or_default().trim();
+        meta.set(key.trim(), value).map_err(|err| format!("metadata line {}: {}", number + 1, err))?;
+    }
+
+    let sidecar = metadata_path(Path::new(file_path));
+    let text = match fs::read_to_string(&sidecar) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(meta),
+        Err(err) => return Err(err.into()),
+    };
+    let file = sidecar.file_name().unwrap_or_default().to_string_lossy().to_string();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or_else(|| format!("{} line {}: expected key = value", file, number + 1))?;
+        meta.set(key.trim().trim_matches('"'), value.trim().trim_matches('"')).map_err(|err| format!("{} line {}: {}", file, number + 1, err))?;
+    }
+    Ok(meta)
+}
+
+// Read a JSON, TOML or YAML deck: a list of cards with named fields, or the list under "cards" with the
+// settings of the deck next to it (the same keys as the metadata of a CSV deck):
+//
+//   name: French basics
+//   language: { French: fr }
+//   cards:
+//     - French: La maison
+//       English: The house
+//       tags: [nouns, home]
+//
+// The columns are the field names in the order they first show up, cards without a field get an empty one.
+// Lists like the tags are joined with commas. Markdown decks are notes instead, see markdown::read_deck.
+pub fn read_structured_deck(file_path: &str) -> Result<(CsvData, DeckMeta), Box<dyn Error>> {
+    let text = fs::read_to_string(file_path)?;
+    if is_markdown_deck(Path::new(file_path)) {
+        return Ok(markdown::read_deck(&text)?);
+    }
+    // TOML and YAML go through JSON values, so there is only one kind of deck to make sense of
+    let deck: Value = match extension(Path::new(file_path)).as_str() {
+        "json" => serde_json::from_str(&text)?,
+        "toml" => serde_json::to_value(toml::from_str::<toml::Table>(&text)?)?,
+        _ => serde_json::to_value(serde_yaml::from_str::<serde_yaml::Value>(&text)?)?,
+    };
+    Ok(structured_deck(deck)?)
+}
+
+fn structured_deck(deck: Value) -> Result<(CsvData, DeckMeta), String> {
+    let (cards, settings) = match deck {
+        Value::Array(cards) => (cards, serde_json::Map::new()),
+        Value::Object(mut deck) => match deck.remove("cards") {
+            Some(Value::Array(cards)) => (cards, deck),
+            _ => return Err(String::from("expected a list of cards under \"cards\"")),
+        },
+        _ => return Err(String::from("expected a list of cards")),
+    };
+
+    let mut meta = DeckMeta::default();
+    apply_settings(&mut meta, settings)?;
+
+    let mut headers: Vec<String> = Vec::new();
+    let mut rows = Vec::new();
+    for (number, card) in cards.into_iter().enumerate() {
+        let fields = match card {
+            Value::Object(fields) if !fields.is_empty() => fields,
+            _ => return Err(format!("card {}: expected named fields", number + 1)),
+        };
+        let mut row = vec![String::new(); headers.len()];
+        for (name, value) in fields {
+            let column = headers.iter().position(|header| *header == name).unwrap_or_else(|| {
+                headers.push(name);
+                row.push(String::new());
+                headers.len() - 1
+            });
+            row[column] = field_text(&value);
+        }
+        rows.push(row);
+    }
+    for row in rows.iter_mut() {
+        row.resize(headers.len(), String::new());
+    }
+    Ok(((headers, rows), meta))
+}
+
+// Settings of a deck given as JSON values, like the ones next to the cards of a JSON deck
+pub(crate) fn apply_settings(meta: &mut DeckMeta, settings: serde_json::Map<String, Value>) -> Result<(), String> {
+    for (key, value) in settings {
+        match value {
+            // language = { French = "fr" } is the same as language.French = "fr"
+            Value::Object(table) => {
+                for (column, value) in table {
+                    meta.set(&format!("{}.{}", key, column), &field_text(&value))?;
+                }
+            }
+            value => meta.set(&key, &field_text(&value))?,
+        }
+    }
+    Ok(())
+}
+
+// Text of a field or setting as it would be written in a CSV deck
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(field_text).collect::<Vec<String>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+
+
+
+
+
+    #[test]
+    fn test_read_csv_with_headers_keeps_all_rows_in_order() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "Question;Answer\nOne;1\nTwo;2\nThree;3").unwrap();
+
+        let (headers, rows) = read_csv_with_headers(temp_file.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(headers, vec!["Question", "Answer"]);
+        assert_eq!(rows.iter().map(|r| r[0].as_str()).collect::<Vec<&str>>(), vec!["One", "Two", "Three"]);
+    }
+
+    #[test]
+    fn test_read_csv_with_headers_detects_the_delimiter() {
+        let dir = tempfile::tempdir().unwrap();
+        for text in ["Question,Answer\nWhat is Rust?,A language; a fast one\n", "Question\tAnswer\nWhat is Rust?\tA language; a fast one\n"] {
+            let path = dir.path().join("deck.csv");
+            fs::write(&path, text).unwrap();
+            let (headers, rows) = read_csv_with_headers(path.to_str().unwrap()).unwrap();
+            assert_eq!(headers, vec!["Question", "Answer"]);
+            assert_eq!(rows, vec![vec!["What is Rust?", "A language; a fast one"]]);
+        }
+    }
+
+    #[test]
+    fn test_is_supported_deck() {
+        assert!(is_supported_deck(Path::new("csv-test/test-fr-en.csv")));
+        assert!(is_supported_deck(Path::new("DECK.CSV")));
+        assert!(!is_supported_deck(Path::new("huhcat.gif")));
+        assert!(!is_supported_deck(Path::new("README")));
+        assert!(is_supported_deck(Path::new("words.json")) && is_supported_deck(Path::new("words.yml")));
+        // The metadata of a deck isn't one
+        assert!(is_supported_deck(Path::new("words.toml")));
+        assert!(!is_supported_deck(Path::new("french.csv.toml")));
+        assert!(is_markdown_deck(Path::new("notes.md")) && is_supported_deck(Path::new("notes.markdown")));
+    }
+
+    #[test]
+    fn test_structured_decks() {
+        let dir = tempfile::tempdir().unwrap();
+        let decks = [
+            ("fr.json", r#"{"name": "French basics", "language": {"French": "fr"}, "cards": [
+                {"French": "La maison", "English": "The house", "tags": ["nouns", "home"]},
+                {"French": "Le chien", "English": "The dog", "Gender": "m"}
+            ]}"#),
+            ("fr.toml", "name = \"French basics\"\nlanguage = { French = \"fr\" }\n\n[[cards]]\nFrench = \"La maison\"\nEnglish = \"The house\"\ntags = [\"nouns\", \"home\"]\n\n[[cards]]\nFrench = \"Le chien\"\nEnglish = \"The dog\"\nGender = \"m\"\n"),
+            ("fr.yaml", "name: French basics\nlanguage:\n  French: fr\ncards:\n  - French: La maison\n    English: The house\n    tags: [nouns, home]\n  - French: Le chien\n    English: The dog\n    Gender: m\n"),
+        ];
+        for (name, text) in decks {
+            let path = dir.path().join(name);
+            fs::write(&path, text).unwrap();
+            let path = path.to_str().unwrap();
+
+            let (headers, rows) = read_csv_with_headers(path).unwrap();
+            assert_eq!(headers, vec!["French", "English", "tags", "Gender"], "{}", name);
+            assert_eq!(rows[0], vec!["La maison", "The house", "nouns, home", ""], "{}", name);
+            assert_eq!(rows[1], vec!["Le chien", "The dog", "", "m"], "{}", name);
+
+            let meta = read_metadata(path).unwrap();
+            assert_eq!((meta.name.as_deref(), meta.language("French")), (Some("French basics"), Some("fr")), "{}", name);
+            assert!(read_deck_with_style(path).is_err());
+        }
+
+        // A plain list of cards works too, other shapes don't
+        let path = dir.path().join("list.json");
+        fs::write(&path, r#"[{"Question": "What is Rust?", "Answer": "A language"}]"#).unwrap();
+        assert_eq!(read_csv_with_headers(path.to_str().unwrap()).unwrap().1, vec![vec!["What is Rust?", "A language"]]);
+        for text in [r#"{"name": "no cards"}"#, r#"["What is Rust?"]"#, r#"{"cards": [], "colour": "blue"}"#, "not json"] {
+            fs::write(&path, text).unwrap();
+            assert!(read_csv_with_headers(path.to_str().unwrap()).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_write_csv_with_headers_roundtrip() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let headers = vec![String::from("Question"), String::from("Answer")];
+        let rows = vec![vec![String::from("Semi;colon?"), String::from("Quoted \"answer\"")]];
+
+        write_csv_with_headers(temp_file.path(), &headers, &rows).unwrap();
+        let (read_headers, read_rows) = read_csv_with_headers(temp_file.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(read_headers, headers);
+        assert_eq!(read_rows, rows);
+    }
+
+    #[test]
+    fn test_apply_direction() {
+        let headers = vec![String::from("English"), String::from("French"), String::from("German")];
+        let rows = vec![vec![String::from("The house"), String::from("La maison"), String::from("Das Haus")]];
+
+        // German asks, English answers
+        let (headers, rows) = apply_direction(headers.clone(), rows.clone(), &[2, 0]).unwrap();
+        assert_eq!(headers, vec!["German", "English"]);
+        assert_eq!(rows[0], vec!["Das Haus", "The house"]);
+
+        // Out of range and single columns are refused
+        assert!(apply_direction(headers.clone(), rows.clone(), &[0, 5]).is_err());
+        assert!(apply_direction(headers, rows, &[0]).is_err());
+    }
+
+    #[test]
+    fn test_read_csv_with_delimiter() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "Question\tAnswer\nWhat is Rust?\tA language").unwrap();
+
+        let (headers, rows) = read_csv_with_delimiter(temp_file.path().to_str().unwrap(), b'\t').unwrap();
+        assert_eq!(headers, vec!["Question", "Answer"]);
+        assert_eq!(rows[0], vec!["What is Rust?", "A language"]);
+    }
+
+    #[test]
+    fn test_detect_style() {
+        assert_eq!(detect_style("Question;Answer\nWhat is Rust?;A language\n"), CsvStyle::default());
+        assert_eq!(detect_style(""), CsvStyle::default());
+
+        let style = detect_style("\"Question\",\"Answer\"\r\n\"What is Rust?\",\"A language, mostly\"\r\n");
+        assert_eq!(style, CsvStyle { delimiter: b',', quote_all: true, crlf: true });
+
+        // One quoted field doesn't make the whole file quoted
+        assert!(!detect_style("Question\tAnswer\n\"Rust\"\tA language\n").quote_all);
+        assert_eq!(detect_style("Question\tAnswer\n").delimiter, b'\t');
+    }
+
+    #[test]
+    fn test_write_csv_with_style_roundtrip() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "\"Question\",\"Answer\"\r\n\"What is Rust?\",\"A language\"\r\n").unwrap();
+        let path = temp_file.path().to_str().unwrap();
+
+        let ((headers, mut rows), style) = read_deck_with_style(path).unwrap();
+        rows.push(vec![String::from("What is Cargo?"), String::from("A tool")]);
+        write_csv_with_style(temp_file.path(), &headers, &rows, style).unwrap();
+
+        let text = fs::read_to_string(path).unwrap();
+        assert_eq!(text, "\"Question\",\"Answer\"\r\n\"What is Rust?\",\"A language\"\r\n\"What is Cargo?\",\"A tool\"\r\n");
+    }
+
+    #[test]
+    fn test_write_csv_with_style_leaves_no_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path().join("deck.csv");
+        fs::write(&deck, "Question;Answer\nWhat is Rust?;A language\n").unwrap();
+
+        let headers = vec![String::from("Question"), String::from("Answer")];
+        write_csv_with_style(&deck, &headers, &[vec![String::from("What is Cargo?"), String::from("A tool")]], CsvStyle::default()).unwrap();
+        assert_eq!(fs::read_to_string(&deck).unwrap(), "Question;Answer\nWhat is Cargo?;A tool\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_read_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path().join("fr.csv");
+        fs::write(&deck, "#name: French basics\n#direction: 1:0   # English first\n# Exported from somewhere: ignored\n# Source: https://example.com/french\n#TODO: more animals\n#language.French: fr\nFrench;English\nLa maison;The house\n").unwrap();
+        let path = deck.to_str().unwrap();
+
+        let meta = read_metadata(path).unwrap();
+        assert_eq!(meta.name.as_deref(), Some("French basics"));
+        assert_eq!(meta.direction, Some(vec![1, 0]));
+        assert_eq!(meta.language("French"), Some("fr"));
+        assert_eq!(read_csv_with_headers(path).unwrap(), (vec![String::from("French"), String::from("English")], vec![vec![String::from("La maison"), String::from("The house")]]));
+
+        // The sidecar wins over the header lines
+        fs::write(metadata_path(&deck), "name = \"Le français\"\nauthor = Jo\nsuspend_leeches = true\n").unwrap();
+        let meta = read_metadata(path).unwrap();
+        assert_eq!((meta.name.as_deref(), meta.author.as_deref(), meta.suspend_leeches), (Some("Le français"), Some("Jo"), Some(true)));
+        assert_eq!(meta.direction, Some(vec![1, 0]));
+
+        fs::write(metadata_path(&deck), "colour = blue\n").unwrap();
+        assert!(read_metadata(path).unwrap_err().to_string().contains("fr.csv.toml line 1"));
+        fs::remove_file(metadata_path(&deck)).unwrap();
+        fs::write(&deck, "#retry_after: soon\nFrench;English\n").unwrap();
+        assert!(read_metadata(path).is_err());
+        fs::write(&deck, "French;English\n").unwrap();
+        assert_eq!(read_metadata(path).unwrap(), DeckMeta::default());
+    }
+
+    #[test]
+    fn test_metadata_lines_survive_writes() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "#name: Rust\nQuestion;Answer\nWhat is Rust?;A language\n").unwrap();
+        let path = temp_file.path().to_str().unwrap();
+
+        replace_row(path, &[0, 1], &[String::from("What is Rust?"), String::from("A language")], &[String::from("What is Rust?"), String::from("A systems language")]).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "#name: Rust\nQuestion;Answer\nWhat is Rust?;A systems language\n");
+    }
+
+    #[test]
+    fn test_replace_row() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "English;French;German\nThe house;La maison;Das Haus\nThe dog;Le chien;Der Hund\n").unwrap();
+        let path = temp_file.path().to_str().unwrap();
+
+        // The card was studied German first
+        let old = [String::from("Der Hund"), String::from("The dog")];
+        let new = [String::from("Der Hund"), String::from("The dog!")];
+        replace_row(path, &[2, 0], &old, &new).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "English;French;German\nThe house;La maison;Das Haus\nThe dog!;Le chien;Der Hund\n");
+
+        assert!(replace_row(path, &[2, 0], &old, &new).is_err());
+    }
+}
@@ -0,0 +1,16 @@
+// The deck handling, progress and scheduling without any of the terminal UI,
+// so other frontends (or scripts) can use trashcards as a library
+pub mod added;
+pub mod config;
+pub mod file_handler;
+pub mod library;
+pub mod lint;
+pub mod markdown;
+pub mod progress;
+pub mod query;
+pub mod recent;
+pub mod reviews;
+pub mod scheduler;
+pub mod session;
+pub mod stats;
+pub mod tags;
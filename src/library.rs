@@ -0,0 +1,201 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::added::AddedStore;
+use crate::file_handler::{self, DeckMeta};
+use crate::progress::{self, ProgressStore};
+use crate::recent::{self, PathList};
+use crate::reviews;
+
+// File inside the data dir listing every registered deck
+const LIBRARY_FILE: &str = "library.txt";
+
+// Counts shown for each deck in the library
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeckSummary {
+    pub path: String,
+    pub cards: usize,
+    pub new: usize,
+    pub due: usize,
+    pub learned: usize,
+    pub last_studied: Option<u64>, // Day of the most recent review of any card in the deck
+    pub error: Option<String>,     // Set when the deck couldn't be read
+    pub meta: DeckMeta,            // Name, description and author the deck gives itself
+}
+
+impl DeckSummary {
+    pub fn load(path: &str, store: &ProgressStore, today: u64) -> Self {
+        let mut summary = DeckSummary { path: path.to_string(), ..DeckSummary::default() };
+        let deck = file_handler::read_metadata(path).and_then(|meta| Ok((meta, file_handler::read_csv_with_headers(path)?)));
+        let rows = match deck {
+            Ok((meta, (_, rows))) => {
+                summary.meta = meta;
+                rows
+            }
+            Err(err) => {
+                summary.error = Some(err.to_string());
+                return summary;
+            }
+        };
+
+        summary.cards = rows.len();
+        for question in rows.iter().filter_map(|row| row.first()) {
+            match store.get(path, question) {
+                None => summary.new += 1,
+                Some(p) => {
+                    if p.is_due(today) {
+                        summary.due += 1;
+                    } else if p.is_learned() {
+                        summary.learned += 1;
+                    }
+                    summary.last_studied = summary.last_studied.max(Some(p.last_review));
+                }
+            }
+        }
+        summary
+    }
+}
+
+// Registered decks, most recently played first. Decks played before the library existed are added from the recent list
+pub fn registered_decks(data_dir: &Path) -> PathList {
+    let mut decks = PathList::load(data_dir.join(LIBRARY_FILE), None);
+    for deck in recent::recent_decks(data_dir).entries {
+        if !decks.contains(&deck) {
+            decks.entries.push(deck);
+        }
+    }
+    decks
+}
+
+// Rename a deck file inside its directory and move its progress, reviews, added days and recent entry along.
+// Returns the key of the renamed deck.
+pub fn rename_deck(data_dir: Option<&Path>, old: &str, new_name: &str) -> Result<String, Box<dyn Error>> {
+    if new_name.is_empty() || new_name.contains('/') {
+        return Err("The new name has to be a plain file name".into());
+    }
+    let new_path = Path::new(old).with_file_name(new_name);
+    if new_path.exists() {
+        return Err(format!("{} already exists", new_path.display()).into());
+    }
+    fs::rename(old, &new_path)?;
+    let new = progress::deck_key(&new_path);
+
+    if let Some(data_dir) = data_dir {
+        let mut store = ProgressStore::load(data_dir);
+        store.rename_deck(old, &new);
+        store.save()?;
+        reviews::rename_deck(&reviews::log_path(data_dir), old, &new)?;
+        let mut added = AddedStore::load(data_dir);
+        added.rename_deck(old, &new);
+        added.save()?;
+
+        let mut recent_decks = recent::recent_decks(data_dir);
+        if let Some(entry) = recent_decks.entries.iter_mut().find(|e| *e == old) {
+            *entry = new.clone();
+            recent_decks.save()?;
+        }
+    }
+    Ok(new)
+}
+
+// Write a deck with its progress columns appended to another CSV file
+pub fn export_deck(deck: &str, store: &ProgressStore, target: &Path) -> Result<(), Box<dyn Error>> {
+    let (mut headers, mut rows) = file_handler::read_csv_with_headers(deck)?;
+
+    headers.extend(["due", "interval", "reps", "lapses"].map(String::from));
+    for row in rows.iter_mut() {
+        let progress = row.first().and_then(|question| store.get(deck, question));
+        match progress {
+            Some(p) => row.extend([progress::format_day(p.due), p.interval.to_string(), p.reps.to_string(), p.lapses.to_string()]),
+            None => row.extend(["", "", "", ""].map(String::from)),
+        }
+    }
+
+    file_handler::write_csv_with_headers(target, &headers, &rows)
+}
+
+// Default export target next to the deck: deck.csv -> deck-export.csv
+pub fn export_path(deck: &Path) -> PathBuf {
+    let stem = deck.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    deck.with_file_name(format!("{}-export.csv", stem))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reviews::Review;
+
+    // Helper to create a deck in a temporary directory
+    fn deck() -> (tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path().join("fr.csv");
+        fs::write(&deck, "French;English\nLa maison;The house\nLe chien;The dog\nLa voiture;The car\n").unwrap();
+        let deck = progress::deck_key(&deck);
+        (dir, deck)
+    }
+
+    #[test]
+    fn test_summary_counts() {
+        let (dir, deck) = deck();
+        let today = progress::today();
+
+        let mut store = ProgressStore::load(dir.path());
+        store.record(&deck, "La maison", false, today); // Due again today
+        store.record(&deck, "Le chien", true, today);   // Due tomorrow, still learning
+
+        let summary = DeckSummary::load(&deck, &store, today);
+        assert_eq!(summary.cards, 3);
+        assert_eq!(summary.new, 1);
+        assert_eq!(summary.due, 1);
+        assert_eq!(summary.learned, 0);
+        assert_eq!(summary.last_studied, Some(today));
+    }
+
+    #[test]
+    fn test_registered_decks_include_recent_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("library.txt"), "/decks/a.csv\n").unwrap();
+        fs::write(dir.path().join("recent.txt"), "/decks/b.csv\n/decks/a.csv\n").unwrap();
+
+        assert_eq!(registered_decks(dir.path()).entries, vec!["/decks/a.csv", "/decks/b.csv"]);
+    }
+
+    #[test]
+    fn test_rename_keeps_progress() {
+        let (dir, deck) = deck();
+        let mut store = ProgressStore::load(dir.path());
+        store.record(&deck, "Le chien", true, 10);
+        store.save().unwrap();
+        let review = Review { deck: deck.clone(), card: String::from("Le chien"), day: 10, correct: true, millis: 900 };
+        reviews::append(&reviews::log_path(dir.path()), &review).unwrap();
+        let mut added = AddedStore::load(dir.path());
+        added.note(&deck, "Le chien", 9);
+        added.save().unwrap();
+
+        assert!(rename_deck(Some(dir.path()), &deck, "sub/dir.csv").is_err());
+        let new = rename_deck(Some(dir.path()), &deck, "french.csv").unwrap();
+
+        assert_eq!(new, progress::deck_key(&dir.path().join("french.csv")));
+        assert!(!Path::new(&deck).exists());
+        assert!(ProgressStore::load(dir.path()).get(&new, "Le chien").is_some());
+        assert_eq!(reviews::load(&reviews::log_path(dir.path())), vec![Review { deck: new.clone(), ..review }]);
+        assert_eq!(AddedStore::load(dir.path()).get(&new, "Le chien"), Some(9));
+        assert_eq!(AddedStore::load(dir.path()).get(&deck, "Le chien"), None);
+    }
+
+    #[test]
+    fn test_export_with_progress() {
+        let (dir, deck) = deck();
+        let mut store = ProgressStore::default();
+        store.record(&deck, "Le chien", false, 10);
+
+        let target = export_path(Path::new(&deck));
+        assert_eq!(target, dir.path().join("fr-export.csv"));
+        export_deck(&deck, &store, &target).unwrap();
+
+        let (headers, rows) = file_handler::read_csv_with_headers(target.to_str().unwrap()).unwrap();
+        assert_eq!(headers, vec!["French", "English", "due", "interval", "reps", "lapses"]);
+        assert_eq!(rows[1], vec!["Le chien", "The dog", "1970-01-11", "0", "1", "1"]);
+        assert_eq!(rows[0][2], "");
+    }
+}
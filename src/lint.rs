@@ -0,0 +1,221 @@
+use csv::ReaderBuilder;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use crate::file_handler;
+use crate::tags;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+// A single problem found in a deck, line 0 means the file as a whole
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    pub line: u64,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}: {}", self.line, severity, self.message)
+    }
+}
+
+// Check a deck for problems that break or confuse the game
+pub fn lint_deck(path: &Path) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |line: u64, severity: Severity, message: String| issues.push(LintIssue { line, severity, message });
+
+    if !file_handler::is_supported_deck(path) {
+        issue(0, Severity::Error, format!("not a supported deck, expected one of: {}", file_handler::SUPPORTED_EXTENSIONS.join(", ")));
+        return issues;
+    }
+
+    if file_handler::is_structured_deck(path) {
+        lint_structured_deck(path, &mut issue);
+        return issues;
+    }
+
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            issue(0, Severity::Error, err.to_string());
+            return issues;
+        }
+    };
+    if let Err(err) = file_handler::read_metadata(&path.to_string_lossy()) {
+        issue(0, Severity::Error, err.to_string());
+    }
+    // Lines are counted in the whole file, metadata lines included
+    let (metadata, csv) = file_handler::split_metadata(&text);
+    let offset = metadata.lines().count() as u64;
+
+    // Flexible so every row with the wrong number of fields can be reported instead of stopping at the first
+    let mut rdr = ReaderBuilder::new().delimiter(file_handler::detect_style(csv).delimiter).flexible(true).from_reader(csv.as_bytes());
+
+    let headers = match rdr.headers() {
+        Ok(headers) => headers.clone(),
+        Err(err) => {
+            issue(offset + 1, Severity::Error, err.to_string());
+            return issues;
+        }
+    };
+    if headers.len() < 2 {
+        issue(offset + 1, Severity::Error, String::from("a deck needs a question column and at least one answer column"));
+    }
+    for header in headers.iter() {
+        if header.trim().is_empty() {
+            issue(offset + 1, Severity::Error, String::from("empty column name"));
+        } else if header.trim() != header {
+            issue(offset + 1, Severity::Warning, format!("column name {:?} has surrounding whitespace", header));
+        }
+    }
+
+    let tags_column = headers.iter().position(|header| header.trim().eq_ignore_ascii_case(tags::TAGS_COLUMN));
+    let mut questions: HashMap<String, u64> = HashMap::new();
+    let mut rows = 0;
+    for result in rdr.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(err) => {
+                let line = err.position().map(|p| offset + p.line()).unwrap_or(0);
+                issue(line, Severity::Error, err.to_string());
+                continue;
+            }
+        };
+        let line = record.position().map(|p| offset + p.line()).unwrap_or(0);
+        rows += 1;
+
+        if record.len() != headers.len() {
+            issue(line, Severity::Error, format!("expected {} fields, found {}", headers.len(), record.len()));
+        }
+        let question = record.get(0).unwrap_or_default().trim();
+        if question.is_empty() {
+            issue(line, Severity::Error, String::from("empty question"));
+        } else if let Some(first) = questions.insert(question.to_string(), line) {
+            // Progress is stored per question, so duplicates share it
+            issue(line, Severity::Warning, format!("duplicate question {:?}, first seen on line {}", question, first));
+        }
+        // Cards without tags are fine
+        for (i, field) in record.iter().enumerate().skip(1).filter(|(i, _)| Some(*i) != tags_column) {
+            if field.trim().is_empty() {
+                let column = headers.get(i).unwrap_or_default();
+                issue(line, Severity::Warning, format!("empty answer in column {:?}", column));
+            }
+        }
+    }
+
+    if rows == 0 {
+        issue(0, Severity::Warning, String::from("the deck has no cards"));
+    }
+    issues
+}
+
+// JSON, TOML and YAML decks have no lines to point at, their problems name the card instead
+fn lint_structured_deck(path: &Path, issue: &mut impl FnMut(u64, Severity, String)) {
+    let (headers, rows) = match file_handler::read_csv_with_headers(&path.to_string_lossy()) {
+        Ok(deck) => deck,
+        Err(err) => {
+            issue(0, Severity::Error, err.to_string());
+            return;
+        }
+    };
+    if headers.len() < 2 {
+        issue(0, Severity::Error, String::from("a deck needs a question column and at least one answer column"));
+    }
+
+    let tags_column = tags::tags_column(&headers);
+    let mut questions: HashMap<String, usize> = HashMap::new();
+    for (number, row) in rows.iter().enumerate().map(|(i, row)| (i + 1, row)) {
+        let question = row.first().map(|q| q.trim()).unwrap_or_default();
+        if question.is_empty() {
+            issue(0, Severity::Error, format!("card {}: empty question", number));
+        } else if let Some(first) = questions.insert(question.to_string(), number) {
+            issue(0, Severity::Warning, format!("card {}: duplicate question {:?}, first seen in card {}", number, question, first));
+        }
+        for (i, field) in row.iter().enumerate().skip(1).filter(|(i, _)| Some(*i) != tags_column) {
+            if field.trim().is_empty() {
+                issue(0, Severity::Warning, format!("card {}: empty answer in column {:?}", number, headers[i]));
+            }
+        }
+    }
+
+    if rows.is_empty() {
+        issue(0, Severity::Warning, String::from("the deck has no cards"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint_str(content: &str) -> Vec<LintIssue> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.csv");
+        fs::write(&path, content).unwrap();
+        lint_deck(&path)
+    }
+
+    #[test]
+    fn test_clean_deck() {
+        assert!(lint_str("Question;Answer\nWhat is Rust?;A language\n").is_empty());
+        // An empty tags column isn't a missing answer
+        assert!(lint_str("Question;Answer;Tags\nWhat is Rust?;A language;\n").is_empty());
+    }
+
+    #[test]
+    fn test_problems_are_reported_per_line() {
+        let issues = lint_str("Question;Answer  \nWhat is Rust?;A language\n;No question\nWhat is Rust?;\nToo;many;fields\n");
+
+        let found: Vec<(u64, Severity)> = issues.iter().map(|i| (i.line, i.severity)).collect();
+        assert_eq!(found, vec![
+            (1, Severity::Warning), // Whitespace in "Answer  "
+            (3, Severity::Error),   // Empty question
+            (4, Severity::Warning), // Duplicate question
+            (4, Severity::Warning), // Empty answer
+            (5, Severity::Error),   // Field count
+        ]);
+    }
+
+    #[test]
+    fn test_metadata_lines() {
+        // Lines still count from the top of the file
+        let issues = lint_str("#name: Rust\n#author: Jo\nQuestion;Answer\nWhat is Rust?;\n");
+        assert_eq!(issues.iter().map(|i| i.line).collect::<Vec<u64>>(), vec![4]);
+
+        let issues = lint_str("#leech_lapses: lots\nQuestion;Answer\nWhat is Rust?;A language\n");
+        assert_eq!((issues[0].line, issues[0].severity), (0, Severity::Error));
+        assert!(issues[0].message.contains("metadata line 1"));
+    }
+
+    #[test]
+    fn test_structured_decks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.json");
+        fs::write(&path, r#"[{"Question": "What is Rust?", "Answer": ""}, {"Question": "What is Rust?", "Answer": "A language"}]"#).unwrap();
+        let messages: Vec<String> = lint_deck(&path).into_iter().map(|i| i.message).collect();
+        assert_eq!(messages, vec!["card 1: empty answer in column \"Answer\"", "card 2: duplicate question \"What is Rust?\", first seen in card 1"]);
+
+        fs::write(&path, "{").unwrap();
+        assert_eq!(lint_deck(&path)[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_single_column_and_unsupported_files() {
+        let issues = lint_str("Question\nWhat is Rust?\n");
+        assert_eq!(issues[0].severity, Severity::Error);
+
+        let issues = lint_deck(Path::new("huhcat.gif"));
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("not a supported deck"));
+    }
+}
@@ -4,16 +4,111 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::{io, thread, time::Duration};
+use std::{io, path::Path, process, thread, time::Duration};
 use crossterm::event::{EnableMouseCapture, DisableMouseCapture};
 use app::file_browser::{FileBrowser}; // Import FileBrowser module
 use app::game::{Game};
 use app::key_handler::{self, AppState};
+use app::library::Library;
+use app::replay::{self, Recorder, Script};
+use rand::{rngs::StdRng, SeedableRng};
+use tui::layout::Rect;
+use trashcards::config::Config;
+use trashcards::session::Session;
+use clap::Parser;
+use cli::{AppOptions, Cli, Command};
 mod app;
+mod cli;
+mod plain;
 
-fn main() -> Result<(), io::Error> {
-    // Setup terminal
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cli = Cli::parse();
+    let options = cli.options();
+
+    // A replay starts with the command line of the recording, in a copy of the data dir
+    let mut script = options.replay.as_deref().map(Script::load).transpose()?.unwrap_or_default();
+    if script.command.is_empty() {
+        script.command = replay::recorded_command(std::env::args().skip(1));
+    } else if cli.command.is_some() {
+        return Err("the recording has its own command, replay it with just --replay".into());
+    } else {
+        cli.command = Cli::try_parse_from(std::iter::once(String::from("trashcards")).chain(script.command.clone()))?.command;
+    }
+    let scratch = options.replay.as_ref().map(|_| replay::scratch_data_dir(&trashcards::progress::data_dir())).transpose()?;
+    let data_dir = scratch.as_ref().map_or_else(trashcards::progress::data_dir, |dir| dir.path().to_path_buf());
+
+    // Command line options win over the config file, the tools don't read it
+    let load_config = || -> Result<Config, Box<dyn std::error::Error>> {
+        let mut config = Config::load(&data_dir)?;
+        config.seed = options.seed.or(config.seed);
+        config.shuffle = options.shuffle.unwrap_or(config.shuffle);
+        Ok(config)
+    };
+
+    match cli.command {
+        None => run_app(None, &options, script, &load_config()?, &data_dir)?,
+        Some(Command::Study { direction: Some(_), ref decks, .. }) if decks.len() > 1 => {
+            return Err("--direction only works with a single deck".into());
+        }
+        Some(Command::Study { decks, size, direction, tags, plain: true, .. }) => {
+            let config = load_config()?;
+            let mut rng = config.seed.map(StdRng::seed_from_u64).unwrap_or_else(StdRng::from_entropy);
+            let mut session = match decks.as_slice() {
+                [deck] => {
+                    let deck = deck.to_str().ok_or("deck path is not valid UTF-8")?;
+                    Session::load(&data_dir, deck, size, direction.as_ref().map(|d| d.0.as_slice()), tags.as_ref(), config.shuffle, &mut rng)?
+                }
+                _ => Session::load_decks(&data_dir, &cli::deck_keys(&decks, &data_dir), size, tags.as_ref(), config.shuffle, &mut rng)?,
+            };
+            session.configure(config.retry_after, config.leech_lapses, config.suspend_leeches);
+            plain::run(&mut session, io::stdin().lock(), io::stdout())?;
+        }
+        Some(Command::Custom { query, decks, size, plain: true, .. }) => {
+            let config = load_config()?;
+            let decks = cli::deck_keys(&cli::find_decks(&decks), &data_dir);
+            let mut rng = config.seed.map(StdRng::seed_from_u64).unwrap_or_else(StdRng::from_entropy);
+            let mut session = Session::query(&data_dir, &decks, &query, size, config.shuffle, &mut rng);
+            session.configure(config.retry_after, config.leech_lapses, config.suspend_leeches);
+            plain::run(&mut session, io::stdin().lock(), io::stdout())?;
+        }
+        Some(Command::Tool(tool)) => {
+            // Non-interactive commands report problems through the exit code
+            if !cli::run(tool, &data_dir)? {
+                process::exit(1);
+            }
+        }
+        Some(study) => run_app(Some(study), &options, script, &load_config()?, &data_dir)?,
+    }
+    Ok(())
+}
+
+// Run the full-screen app, optionally starting right in a game or custom study session.
+// A replayed recording is fed in before any live input, a recording captures everything that follows.
+fn run_app(study: Option<Command>, options: &AppOptions, script: Script, config: &Config, data_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    // Check the decks while the terminal still shows errors normally
+    match study {
+        Some(Command::Study { ref decks, .. }) => {
+            if let Some(deck) = decks.iter().find(|deck| !deck.is_file()) {
+                return Err(format!("{} is not a file", deck.display()).into());
+            }
+        }
+        Some(Command::Custom { ref decks, .. }) => {
+            if let Some(path) = decks.iter().find(|path| !path.exists()) {
+                return Err(format!("{} does not exist", path.display()).into());
+            }
+        }
+        _ => {}
+    }
+
+    let seed = script.seed.or(config.seed).unwrap_or_else(rand::random);
+    let shuffle = script.shuffle.unwrap_or(config.shuffle);
+    let (width, height) = crossterm::terminal::size()?;
+    let mut recorder = options.record.as_deref().map(|path| Recorder::create(path, &script.command, seed, shuffle, width, height)).transpose()?;
+    let mut replayed = script.events.into_iter();
+
+    // Setup terminal, the guard restores it however the app ends
     enable_raw_mode()?;
+    let _guard = TerminalGuard;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
@@ -24,34 +119,76 @@ fn main() -> Result<(), io::Error> {
 
     // Initialize FileBrowser instance
     let mut file_browser = FileBrowser::new();
+    file_browser.load_history(data_dir);
     let mut game = Game::new();
+    game.data_dir = Some(data_dir.to_path_buf());
+    game.rng = StdRng::seed_from_u64(seed);
+    game.shuffle = shuffle;
+    game.retry_after = config.retry_after;
+    game.leech_lapses = config.leech_lapses;
+    game.suspend_leeches = config.suspend_leeches;
+    let mut library = Library::new();
+    library.leech_lapses = config.leech_lapses;
+    library.load(data_dir);
+
+    match study {
+        // `trashcards study deck.csv` skips the menus, several decks are marked in the library and studied together
+        Some(Command::Study { decks, size, direction, tags, .. }) => {
+            game.session_size = size;
+            game.columns = direction.map(|d| d.0);
+            library.filter = tags;
+            current_state = match decks.as_slice() {
+                [deck] => key_handler::start_deck(&trashcards::progress::deck_key(deck), &mut file_browser, &mut game, &mut library),
+                _ => {
+                    library.marked = cli::deck_keys(&decks, data_dir);
+                    key_handler::start_decks(&mut game, &mut library)
+                }
+            };
+        }
+        // `trashcards custom QUERY` starts right in the custom study session
+        Some(Command::Custom { query, decks, size, .. }) => {
+            game.session_size = size;
+            game.start_query(cli::deck_keys(&cli::find_decks(&decks), data_dir), query);
+            current_state = AppState::Game;
+        }
+        _ => {}
+    }
+
+    // Mouse hit-testing uses the size of the recorded terminal while replaying
+    let mut size = terminal.size()?;
 
     loop {
         terminal.draw(|f| {
             let size = f.size();
 
             // Call the layout drawing function from app/layout.rs
-            app::layout::draw_layout(f, size, current_state, &file_browser, &game);
+            app::layout::draw_layout(f, size, current_state, &file_browser, &game, &library);
 
         })?;
 
-        // Wait for a key press and handle input
-        if let Event::Key(key) = event::read()? {
-            // Update the state based on key press
-            current_state = key_handler::handle_keypress(key.code, current_state, &mut file_browser, &mut game); 
-
-            // Handle file browser state and keypresses
-            if current_state == AppState::FileBrowser {
-                file_browser.handle_keypress(key.code);  // Handle file browser navigation
-            }
+        // Take the next replayed event, or wait for a key press
+        let event = match replayed.next() {
+            Some(event) => event,
+            None => event::read()?,
+        };
+        if let Some(Err(err)) = recorder.as_mut().map(|recorder| recorder.record(&event)) {
+            library.message = Some(format!("Recording stopped: {}", err));
+            recorder = None;
+        }
+        if let Event::Resize(width, height) = event {
+            size = Rect::new(0, 0, width, height);
+        }
+        current_state = key_handler::handle_event(event, current_state, size, &mut file_browser, &mut game, &mut library);
+        if matches!(current_state, AppState::Exit) {
+            exit_flag = true; // Set the flag to exit the loop
+        }
 
-            // Handle game state and keypress
-            if current_state == AppState::Game {
-                game.handle_keypress(key.code);
-            }
-            if matches!(current_state, AppState::Exit) {
-                exit_flag = true; // Set the flag to exit the loop
+        // The library asked to edit a deck, hand the terminal over to the editor for a moment
+        if let Some(deck) = library.edit_request.take() {
+            if let Err(err) = run_editor(&mut terminal, &deck) {
+                library.message = Some(format!("Error running editor: {}", err));
             }
+            library.refresh();
         }
 
         // If exit_flag is true, break out of the loop
@@ -62,15 +199,31 @@ fn main() -> Result<(), io::Error> {
 
     // Restore terminal state before exiting
     thread::sleep(Duration::from_millis(500));
-
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
     terminal.show_cursor()?;
 
     Ok(())
 }
+
+// Leaves raw mode and the alternate screen when dropped, so an error doesn't leave the terminal unusable
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, crossterm::cursor::Show);
+    }
+}
+
+// Leave the TUI, open the deck in $EDITOR (vi if unset) and come back once it exits
+fn run_editor(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, deck: &Path) -> Result<(), io::Error> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| String::from("vi"));
+    let status = process::Command::new(editor).arg(deck).status();
+
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+    status.map(|_| ())
+}
@@ -0,0 +1,195 @@
+use crate::file_handler::{self, CsvData, DeckMeta};
+
+// Column names of cards written as headings or definitions
+const QUESTION: &str = "Question";
+const ANSWER: &str = "Answer";
+
+// Turn Markdown notes into cards. Each section under a `##` heading is one card, the heading asks
+// and the text below answers, unless the section holds a table or a definition list:
+//
+//   ## What is Rust?
+//   A *systems* programming language.
+//
+//   | French    | English   |
+//   |-----------|-----------|
+//   | La maison | The house |
+//
+//   Cargo
+//   : Rust's package manager
+//
+// A table row is a card with the table's columns, a term is a card with its definitions as the answer.
+// The columns of the first table are the columns of the deck, cards of headings and definitions fill
+// the first two. Code blocks and emphasis stay in the text, so the game can show them.
+// YAML front matter between `---` lines holds the deck's settings, a `#` heading names the deck.
+pub fn read_deck(text: &str) -> Result<(CsvData, DeckMeta), String> {
+    let (settings, body) = front_matter(text);
+    let mut meta = DeckMeta::default();
+    if let Some(settings) = settings {
+        let settings: serde_yaml::Value = serde_yaml::from_str(settings).map_err(|err| format!("front matter: {}", err))?;
+        match serde_json::to_value(settings).map_err(|err| format!("front matter: {}", err))? {
+            serde_json::Value::Object(settings) => file_handler::apply_settings(&mut meta, settings)?,
+            serde_json::Value::Null => {}
+            _ => return Err(String::from("front matter: expected key: value lines")),
+        }
+    }
+
+    let mut headers: Option<Vec<String>> = None;
+    let mut cards = Vec::new();
+    for section in sections(body) {
+        if let Some(title) = section.title.strip_prefix("# ") {
+            meta.name.get_or_insert_with(|| title.trim().to_string());
+        }
+
+        let mut found = false;
+        let mut term: Option<&str> = None; // Last line that could be the term of a definition
+        let mut defining = false;          // The last card came from a definition, so another one adds to it
+        let mut i = 0;
+        while i < section.lines.len() {
+            let line = section.lines[i];
+            i += 1;
+            if section.code[i - 1] {
+                (term, defining) = (None, false);
+            } else if is_table_row(line) && section.lines.get(i).map(|next| is_separator(next)).unwrap_or(false) {
+                // A header row, its separator and the rows below
+                let width = headers.get_or_insert_with(|| table_cells(line)).len();
+                i += 1;
+                while let Some(row) = section.lines.get(i).filter(|row| is_table_row(row)) {
+                    let mut card = table_cells(row);
+                    card.resize(width, String::new());
+                    cards.push(card);
+                    i += 1;
+                }
+                (term, defining, found) = (None, false, true);
+            } else if let Some(definition) = line.strip_prefix(": ").filter(|_| term.is_some() || defining) {
+                match term.take() {
+                    Some(term) => cards.push(vec![term.trim().to_string(), definition.trim().to_string()]),
+                    None => {
+                        if let Some(answer) = cards.last_mut().and_then(|card| card.get_mut(1)) {
+                            answer.push('\n');
+                            answer.push_str(definition.trim());
+                        }
+                    }
+                }
+                (defining, found) = (true, true);
+            } else if !line.trim().is_empty() {
+                (term, defining) = (Some(line), false);
+            }
+        }
+
+        if let (false, Some(question)) = (found, section.title.strip_prefix("## ")) {
+            cards.push(vec![question.trim().to_string(), section.text()]);
+        }
+    }
+
+    let headers = headers.unwrap_or_else(|| vec![String::from(QUESTION), String::from(ANSWER)]);
+    for card in cards.iter_mut() {
+        card.resize(headers.len(), String::new());
+    }
+    Ok(((headers, cards), meta))
+}
+
+// Text of the front matter, if the file starts with one, and the rest of the file
+fn front_matter(text: &str) -> (Option<&str>, &str) {
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return (None, text);
+    };
+    match rest.find("\n---") {
+        Some(end) => {
+            let after = &rest[end + 4..];
+            (Some(&rest[..end]), after.split_once('\n').map(|(_, body)| body).unwrap_or(""))
+        }
+        None => (None, text),
+    }
+}
+
+// Lines under one heading, the lines before the first heading have an empty title
+struct Section<'a> {
+    title: &'a str,
+    lines: Vec<&'a str>,
+    code: Vec<bool>, // Lines inside a code block, which can't start a table or a definition
+}
+
+impl Section<'_> {
+    // The text of the section without the blank lines around it
+    fn text(&self) -> String {
+        let lines: Vec<&str> = self.lines.iter().map(|line| line.trim_end()).collect();
+        let start = lines.iter().position(|line| !line.is_empty()).unwrap_or(lines.len());
+        let end = lines.iter().rposition(|line| !line.is_empty()).map(|i| i + 1).unwrap_or(start);
+        lines[start..end].join("\n")
+    }
+}
+
+// Split the notes at `#` and `##` headings, `#` lines inside code blocks are code
+fn sections(text: &str) -> Vec<Section<'_>> {
+    let mut sections = vec![Section { title: "", lines: Vec::new(), code: Vec::new() }];
+    let mut in_code = false;
+    for line in text.lines() {
+        let fence = line.trim_start().starts_with("```");
+        if !in_code && (line.starts_with("# ") || line.starts_with("## ")) {
+            sections.push(Section { title: line, lines: Vec::new(), code: Vec::new() });
+            continue;
+        }
+        let section = sections.last_mut().expect("there is always a section");
+        section.lines.push(line);
+        section.code.push(in_code || fence);
+        if fence {
+            in_code = !in_code;
+        }
+    }
+    sections
+}
+
+fn is_table_row(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
+// |---|:--:| under the header row of a table
+fn is_separator(line: &str) -> bool {
+    is_table_row(line) && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t')) && line.contains('-')
+}
+
+// "| La maison | The house |" -> ["La maison", "The house"]
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|').map(|cell| cell.trim().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(text: &str) -> CsvData {
+        read_deck(text).unwrap().0
+    }
+
+    #[test]
+    fn test_headings_are_cards() {
+        let (headers, rows) = cards("# Rust\n\nSome notes.\n\n## What is Rust?\n\nA *systems* language.\n\n```rust\n## not a heading\n```\n\n## What is Cargo?\nRust's package manager\n");
+        assert_eq!(headers, vec!["Question", "Answer"]);
+        assert_eq!(rows, vec![
+            vec![String::from("What is Rust?"), String::from("A *systems* language.\n\n```rust\n## not a heading\n```")],
+            vec![String::from("What is Cargo?"), String::from("Rust's package manager")],
+        ]);
+        assert_eq!(read_deck("# Rust\n").unwrap().1.name.as_deref(), Some("Rust"));
+    }
+
+    #[test]
+    fn test_tables_and_definition_lists() {
+        let text = "---\nauthor: Jo\nlanguage:\n  French: fr\n---\n## Words\n\n| French | English | Tags |\n|--------|:-------:|------|\n| La maison | The house | nouns |\n| Le chien | The dog |\n\nCargo\n: Rust's package manager\n: Also a build tool\n\nrustc\n\n: The compiler\n";
+        let ((headers, rows), meta) = read_deck(text).unwrap();
+        assert_eq!(headers, vec!["French", "English", "Tags"]);
+        assert_eq!(rows, vec![
+            vec!["La maison", "The house", "nouns"],
+            vec!["Le chien", "The dog", ""],
+            vec!["Cargo", "Rust's package manager\nAlso a build tool", ""],
+            vec!["rustc", "The compiler", ""],
+        ]);
+        assert_eq!((meta.author.as_deref(), meta.language("French")), (Some("Jo"), Some("fr")));
+
+        // A colon line without a term is just text
+        assert_eq!(cards("## Ratio\n: 1\n").1, vec![vec!["Ratio", ": 1"]]);
+        assert!(read_deck("---\ncolour: blue\n---\n## Q\nA\n").is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use std::io::{self, BufRead, Write};
+use trashcards::session::Session;
+
+// Study without the full-screen app: questions, answers and grades are plain lines,
+// which works over flaky SSH, inside editor shells and with screen readers
+pub fn run<R: BufRead, W: Write>(session: &mut Session, mut input: R, mut output: W) -> io::Result<()> {
+    let total = session.cards.len();
+    if total == 0 {
+        writeln!(output, "The deck has no cards.")?;
+        return Ok(());
+    }
+
+    // A deck that describes itself says so first
+    if let Some(meta) = session.card_meta(0).filter(|_| session.deck_count() == 1) {
+        if let Some(ref name) = meta.name {
+            writeln!(output, "{}{}", name, meta.author.as_ref().map(|author| format!(" by {}", author)).unwrap_or_default())?;
+        }
+        if let Some(ref description) = meta.description {
+            writeln!(output, "{}", description)?;
+        }
+    }
+
+    // Files that couldn't be read or saved are reported as soon as they come up
+    let mut warned = print_warnings(&mut output, session, 0)?;
+
+    // Missed cards come back later, they are marked instead of numbered
+    while let Some(card) = session.current_card().cloned() {
+        let position = if session.is_repeat() { String::from("again") } else { format!("{}/{}", session.current + 1, total) };
+        // Cards of several decks say which one they are from
+        let position = match session.deck_name(session.current) {
+            Some(name) if session.deck_count() > 1 => format!("{}, {}", position, name),
+            _ => position,
+        };
+        writeln!(output)?;
+        writeln!(output, "[{}] {}: {}", position, header(session, 0), card[0])?;
+        write!(output, "Type your answer or press Enter to reveal: ")?;
+        output.flush()?;
+        let Some(typed) = read_line(&mut input)? else {
+            break;
+        };
+
+        for (i, answer) in card.iter().enumerate().skip(1) {
+            writeln!(output, "{}: {}", header(session, i), answer)?;
+        }
+
+        // A typed answer that matches one of the columns makes 'yes' the default grade
+        let matched = !typed.is_empty() && card.iter().skip(1).any(|answer| answer.trim().eq_ignore_ascii_case(&typed));
+        if matched {
+            writeln!(output, "Correct!")?;
+        }
+
+        loop {
+            write!(output, "Did you know it? [y]es / [n]o / [s]kip / [u]ndo the last card / [q]uit{}: ", if matched { " (Enter: yes)" } else { "" })?;
+            output.flush()?;
+            let Some(grade) = read_line(&mut input)? else {
+                return summary(&mut output, session);
+            };
+            match grade.to_lowercase().as_str() {
+                "y" | "yes" => session.grade(true),
+                "" if matched => session.grade(true),
+                "n" | "no" => session.grade(false),
+                "s" | "skip" => session.advance(),
+                // Back to the previous card, nothing happens on the first one
+                "u" | "undo" => {
+                    if !session.undo() {
+                        continue;
+                    }
+                }
+                "q" | "quit" => return summary(&mut output, session),
+                _ => continue,
+            }
+            break;
+        }
+        warned = print_warnings(&mut output, session, warned)?;
+    }
+
+    summary(&mut output, session)
+}
+
+// Print the warnings of the session from `from` on, returns how many were printed so far
+fn print_warnings<W: Write>(output: &mut W, session: &Session, from: usize) -> io::Result<usize> {
+    for warning in &session.warnings[from..] {
+        writeln!(output, "{}", warning)?;
+    }
+    Ok(session.warnings.len())
+}
+
+fn header(session: &Session, column: usize) -> String {
+    session.card_labels(session.current).get(column).map(|h| h.trim().to_string()).unwrap_or_default()
+}
+
+// Read one trimmed line, None once the input is closed
+fn read_line<R: BufRead>(input: &mut R) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+fn summary<W: Write>(output: &mut W, session: &Session) -> io::Result<()> {
+    let summary = session.summary();
+    writeln!(output)?;
+    writeln!(output, "Done: you knew {} of {} cards in {}s.", summary.correct, summary.seen, summary.duration.as_secs())?;
+    if !summary.hardest.is_empty() {
+        writeln!(output, "Hardest: {}", summary.hardest.join(", "))?;
+    }
+    if !summary.leeches.is_empty() {
+        writeln!(output, "New leeches, consider rewriting them: {}", summary.leeches.join(", "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        Session::new(
+            vec![String::from("French"), String::from("English")],
+            vec![
+                vec![String::from("La maison"), String::from("The house")],
+                vec![String::from("Le chien"), String::from("The dog")],
+            ],
+        )
+    }
+
+    #[test]
+    fn test_plain_session() {
+        let mut session = session();
+        let mut output = Vec::new();
+
+        // Reveal the first card and fail it, type the second answer and accept the default grade,
+        // then get the first one right when it comes back
+        run(&mut session, "\nn\nthe dog\n\nthe house\n\n".as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("[1/2] French: La maison"));
+        assert!(output.contains("[again] French: La maison"));
+        assert!(output.contains("English: The house"));
+        assert!(output.contains("Correct!"));
+        assert!(output.contains("you knew 2 of 2 cards"));
+        assert!(output.contains("Hardest: La maison, Le chien"));
+        assert_eq!(session.progress.get("", "La maison").unwrap().lapses, 1);
+        assert_eq!(session.progress.get("", "Le chien").unwrap().lapses, 0);
+    }
+
+    #[test]
+    fn test_plain_session_undo() {
+        let mut session = session();
+        let mut output = Vec::new();
+
+        // Fail the first card by mistake, undo on the second one and get the first one right
+        run(&mut session, "\nn\n\nu\n\ny\n\ny\n".as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("[1/2] French: La maison").count(), 2);
+        assert!(output.contains("you knew 2 of 2 cards"));
+        assert_eq!(session.progress.get("", "La maison").unwrap().lapses, 0);
+    }
+
+    #[test]
+    fn test_plain_session_quit_and_closed_input() {
+        let mut session = session();
+        let mut output = Vec::new();
+        run(&mut session, "\nq\n".as_bytes(), &mut output).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("you knew 0 of 0 cards"));
+        assert!(session.progress.get("", "La maison").is_none());
+
+        // Input closing halfway just ends the session
+        let mut output = Vec::new();
+        run(&mut session, "".as_bytes(), &mut output).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("[1/2]"));
+    }
+
+    #[test]
+    fn test_plain_session_prints_warnings() {
+        let mut session = session();
+        session.warnings.push(String::from("Error reading missing.csv: not found"));
+        let mut output = Vec::new();
+        run(&mut session, "\nq\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap().matches("Error reading missing.csv").count(), 1);
+    }
+}
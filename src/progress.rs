@@ -0,0 +1,283 @@
+use csv::{ReaderBuilder, WriterBuilder};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::scheduler::CardProgress;
+
+// Name of the file inside the data dir that holds the progress of every card
+const PROGRESS_FILE: &str = "progress.csv";
+
+// Progress of all cards in all decks, keyed by deck path and question
+#[derive(Debug, Default)]
+pub struct ProgressStore {
+    path: Option<PathBuf>, // No path means the store only lives in memory
+    cards: HashMap<(String, String), CardProgress>,
+}
+
+impl ProgressStore {
+    // Load the store from the data dir, a missing file just means no progress yet
+    pub fn load(data_dir: &Path) -> Self {
+        let path = data_dir.join(PROGRESS_FILE);
+        let mut store = ProgressStore { path: Some(path.clone()), cards: HashMap::new() };
+
+        let Ok(mut rdr) = ReaderBuilder::new().delimiter(b';').from_path(&path) else {
+            return store;
+        };
+        for record in rdr.records().filter_map(|r| r.ok()) {
+            let field = |i: usize| record.get(i).unwrap_or_default();
+            let number = |i: usize| field(i).parse::<u64>().unwrap_or(0);
+            store.cards.insert(
+                (field(0).to_string(), field(1).to_string()),
+                CardProgress {
+                    interval: number(2) as u32,
+                    due: number(3),
+                    reps: number(4) as u32,
+                    lapses: number(5) as u32,
+                    last_review: number(6),
+                    suspended: field(7) == "1", // Files from before suspending existed lack the column
+                },
+            );
+        }
+        store
+    }
+
+    // Write the store back to its file, creating the data dir if needed
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut wtr = WriterBuilder::new().delimiter(b';').from_path(path)?;
+        wtr.write_record(["deck", "card", "interval", "due", "reps", "lapses", "last_review", "suspended"])?;
+        // Sorted so the file doesn't reshuffle on every save
+        let mut keys: Vec<&(String, String)> = self.cards.keys().collect();
+        keys.sort();
+        for key in keys {
+            let p = &self.cards[key];
+            wtr.write_record([
+                key.0.as_str(),
+                key.1.as_str(),
+                &p.interval.to_string(),
+                &p.due.to_string(),
+                &p.reps.to_string(),
+                &p.lapses.to_string(),
+                &p.last_review.to_string(),
+                if p.suspended { "1" } else { "0" },
+            ])?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn get(&self, deck: &str, card: &str) -> Option<&CardProgress> {
+        self.cards.get(&(deck.to_string(), card.to_string()))
+    }
+
+    // Put back a saved state of a card, None forgets its progress
+    pub fn restore(&mut self, deck: &str, card: &str, progress: Option<CardProgress>) {
+        let key = (deck.to_string(), card.to_string());
+        match progress {
+            Some(progress) => self.cards.insert(key, progress),
+            None => self.cards.remove(&key),
+        };
+    }
+
+    // Every card with progress as (deck, card, progress)
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &CardProgress)> {
+        self.cards.iter().map(|((deck, card), p)| (deck.as_str(), card.as_str(), p))
+    }
+
+    // Record an answer for a card, creating its progress on the first review
+    pub fn record(&mut self, deck: &str, card: &str, correct: bool, today: u64) {
+        self.cards
+            .entry((deck.to_string(), card.to_string()))
+            .or_insert_with(|| CardProgress::new(today))
+            .review(correct, today);
+    }
+
+    // Suspend or unsuspend a card, returns false if the card has no progress yet
+    pub fn set_suspended(&mut self, deck: &str, card: &str, suspended: bool) -> bool {
+        match self.cards.get_mut(&(deck.to_string(), card.to_string())) {
+            Some(p) => {
+                p.suspended = suspended;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Cards of all decks with at least `threshold` lapses as (deck, card, progress), most lapses first
+    pub fn leeches(&self, threshold: u32) -> Vec<(&str, &str, &CardProgress)> {
+        let mut leeches: Vec<_> = self.iter().filter(|(_, _, p)| p.is_leech(threshold)).collect();
+        leeches.sort_by(|a, b| b.2.lapses.cmp(&a.2.lapses).then_with(|| (a.0, a.1).cmp(&(b.0, b.1))));
+        leeches
+    }
+
+    // Move the progress of every card of a deck to a new deck key, e.g. after renaming the file
+    pub fn rename_deck(&mut self, old: &str, new: &str) {
+        let moved: Vec<(String, String)> = self.cards.keys().filter(|(deck, _)| deck == old).cloned().collect();
+        for key in moved {
+            if let Some(progress) = self.cards.remove(&key) {
+                self.cards.insert((new.to_string(), key.1), progress);
+            }
+        }
+    }
+
+    // Move the progress of a card whose question was rewritten, an existing entry for the new question wins
+    pub fn rename_card(&mut self, deck: &str, old: &str, new: &str) {
+        let new_key = (deck.to_string(), new.to_string());
+        if self.cards.contains_key(&new_key) {
+            return;
+        }
+        if let Some(progress) = self.cards.remove(&(deck.to_string(), old.to_string())) {
+            self.cards.insert(new_key, progress);
+        }
+    }
+
+    // Number of the given cards that were studied before and are due today
+    pub fn due_count<'a>(&self, deck: &str, cards: impl Iterator<Item = &'a str>, today: u64) -> usize {
+        cards.filter(|card| self.get(deck, card).map(|p| p.is_due(today)).unwrap_or(false)).count()
+    }
+}
+
+// Directory where the app keeps its own files (progress, ...)
+pub fn data_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("TRASHCARDS_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+    if let Ok(dir) = std::env::var("XDG_DATA_HOME") {
+        return PathBuf::from(dir).join("trashcards");
+    }
+    let home = std::env::var("HOME").unwrap_or_else(|_| String::from("."));
+    PathBuf::from(home).join(".local/share/trashcards")
+}
+
+// Days since the Unix epoch
+pub fn today() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() / 86_400).unwrap_or(0)
+}
+
+// Format a day number as YYYY-MM-DD
+pub fn format_day(day: u64) -> String {
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+// Decks are identified by their absolute path, so the same deck opened from elsewhere keeps its progress
+pub fn deck_key(path: &Path) -> String {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()).to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_and_due_count() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(PROGRESS_FILE),
+            "deck;card;interval;due;reps;lapses;last_review\ndeck;a;1;11;1;0;10\ndeck;b;0;10;1;1;10\n",
+        ).unwrap();
+
+        let store = ProgressStore::load(dir.path());
+        assert_eq!(store.get("deck", "b").unwrap().lapses, 1);
+        assert!(store.get("other", "a").is_none());
+
+        let cards = ["a", "b", "never seen"];
+        assert_eq!(store.due_count("deck", cards.iter().copied(), 10), 1);
+        assert_eq!(store.due_count("deck", cards.iter().copied(), 11), 2);
+
+        // No file, no progress
+        assert!(ProgressStore::load(&dir.path().join("missing")).get("deck", "a").is_none());
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = ProgressStore::load(dir.path());
+        store.record("/decks/fr.csv", "La maison", true, 10);
+        store.record("/decks/fr.csv", "Le chien", false, 10);
+        store.save().unwrap();
+
+        // Reload from disk
+        let loaded = ProgressStore::load(dir.path());
+        assert_eq!(loaded.get("/decks/fr.csv", "La maison"), store.get("/decks/fr.csv", "La maison"));
+        assert_eq!(loaded.get("/decks/fr.csv", "Le chien").unwrap().lapses, 1);
+        assert!(loaded.get("/decks/other.csv", "La maison").is_none());
+    }
+
+    #[test]
+    fn test_due_count() {
+        let mut store = ProgressStore::default();
+        store.record("deck", "a", true, 10); // Due on day 11
+        store.record("deck", "b", false, 10); // Due on day 10
+
+        let cards = ["a", "b", "never seen"];
+        assert_eq!(store.due_count("deck", cards.iter().copied(), 10), 1);
+        assert_eq!(store.due_count("deck", cards.iter().copied(), 11), 2);
+    }
+
+    #[test]
+    fn test_leeches_and_suspending() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = ProgressStore::load(dir.path());
+        for lapses in 0..4 {
+            store.record("deck", "harder", false, 10);
+            if lapses < 3 {
+                store.record("deck", "hard", false, 10);
+            }
+        }
+        store.record("deck", "easy", false, 10);
+
+        let leeches: Vec<&str> = store.leeches(3).iter().map(|(_, card, _)| *card).collect();
+        assert_eq!(leeches, vec!["harder", "hard"]);
+        assert!(store.leeches(0).is_empty());
+
+        // Suspending survives a save
+        assert!(store.set_suspended("deck", "hard", true));
+        assert!(!store.set_suspended("deck", "never seen", true));
+        store.save().unwrap();
+        assert!(ProgressStore::load(dir.path()).get("deck", "hard").unwrap().suspended);
+        assert!(!ProgressStore::load(dir.path()).get("deck", "easy").unwrap().suspended);
+    }
+
+    #[test]
+    fn test_rename_deck() {
+        let mut store = ProgressStore::default();
+        store.record("old", "a", true, 10);
+        store.record("other", "a", true, 10);
+        store.rename_deck("old", "new");
+
+        assert!(store.get("old", "a").is_none());
+        assert!(store.get("new", "a").is_some());
+        assert!(store.get("other", "a").is_some());
+
+        store.rename_card("new", "a", "b");
+        assert!(store.get("new", "a").is_none());
+        assert!(store.get("new", "b").is_some());
+    }
+
+    #[test]
+    fn test_format_day() {
+        assert_eq!(format_day(0), "1970-01-01");
+        assert_eq!(format_day(59), "1970-03-01");
+        assert_eq!(format_day(19_723), "2024-01-01");
+        assert_eq!(format_day(19_782), "2024-02-29");
+    }
+}
+
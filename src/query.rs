@@ -0,0 +1,387 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::scheduler::CardProgress;
+
+// What a query looks at to decide whether a card goes into a custom study session
+pub struct CardInfo<'a> {
+    pub deck: &'a str,
+    pub headers: &'a [String],
+    pub fields: &'a [String],
+    pub tags: &'a [String],
+    pub progress: Option<&'a CardProgress>,
+    pub added: Option<u64>, // Day the card was first seen
+    pub today: u64,
+}
+
+// Numbers of a card's progress that can be compared, new cards count as 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Lapses,
+    Reps,
+    Interval,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compare {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+// A single condition of a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Due(u64),                      // due:today, due:tomorrow, due:3d (due within that many days, overdue included)
+    Added(u64),                    // added:today, added:7d (first seen within that many days)
+    Number(Field, Compare, u32),   // lapses>3, reps=0, interval>=21
+    Text(String),                  // "HTTP" or a plain word, anywhere in the card
+    Column(String),                // col:German, the card has something in a column of that name
+    Tag(String),                   // tag:networking
+    Deck(String),                  // deck:french, part of the deck's path
+    New,                           // is:new, never studied
+    Learned,                       // is:learned
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Term(Term),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+// A custom study query like `due:today lapses>3` or `"HTTP" or col:German -tag:basics`.
+// Terms next to each other all have to match, "or" gives alternatives, "not" or '-' negates and parentheses group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    text: String, // As typed, for showing it again
+    expr: Expr,
+}
+
+impl Query {
+    pub fn matches(&self, card: &CardInfo) -> bool {
+        self.expr.matches(card)
+    }
+}
+
+impl Expr {
+    fn matches(&self, card: &CardInfo) -> bool {
+        match self {
+            Expr::Term(term) => term.matches(card),
+            Expr::Not(expr) => !expr.matches(card),
+            Expr::And(left, right) => left.matches(card) && right.matches(card),
+            Expr::Or(left, right) => left.matches(card) || right.matches(card),
+        }
+    }
+}
+
+impl Term {
+    pub fn matches(&self, card: &CardInfo) -> bool {
+        let contains = |haystack: &str, needle: &str| haystack.to_lowercase().contains(&needle.to_lowercase());
+        match self {
+            Term::Due(days) => card.progress.map(|p| p.due <= card.today + days && !p.suspended).unwrap_or(false),
+            Term::Added(days) => card.added.map(|day| day + days >= card.today).unwrap_or(false),
+            Term::Number(field, compare, value) => {
+                let number = card.progress.map(|p| match field {
+                    Field::Lapses => p.lapses,
+                    Field::Reps => p.reps,
+                    Field::Interval => p.interval,
+                }).unwrap_or(0);
+                compare.holds(number, *value)
+            }
+            Term::Text(text) => card.fields.iter().any(|field| contains(field, text)),
+            Term::Column(name) => card.headers.iter().zip(card.fields)
+                .any(|(header, field)| header.trim().eq_ignore_ascii_case(name) && !field.trim().is_empty()),
+            Term::Tag(tag) => card.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Term::Deck(name) => contains(card.deck, name),
+            Term::New => card.progress.is_none(),
+            Term::Learned => card.progress.map(|p| p.is_learned()).unwrap_or(false),
+        }
+    }
+}
+
+impl Compare {
+    fn holds(self, left: u32, right: u32) -> bool {
+        match self {
+            Compare::Less => left < right,
+            Compare::LessOrEqual => left <= right,
+            Compare::Equal => left == right,
+            Compare::GreaterOrEqual => left >= right,
+            Compare::Greater => left > right,
+        }
+    }
+}
+
+// "today" -> 0, "tomorrow" -> 1, "7d" or "7" -> 7
+fn parse_days(value: &str) -> Result<u64, String> {
+    match value {
+        "today" => Ok(0),
+        "tomorrow" => Ok(1),
+        _ => value.strip_suffix('d').unwrap_or(value).parse()
+            .map_err(|_| format!("{:?} is not a number of days (today, tomorrow or like 7d)", value)),
+    }
+}
+
+impl FromStr for Term {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Comparisons first, "lapses>=3" has no colon
+        for (name, field) in [("lapses", Field::Lapses), ("reps", Field::Reps), ("interval", Field::Interval)] {
+            let Some(rest) = s.strip_prefix(name) else {
+                continue;
+            };
+            let (compare, value) = if let Some(value) = rest.strip_prefix(">=") {
+                (Compare::GreaterOrEqual, value)
+            } else if let Some(value) = rest.strip_prefix("<=") {
+                (Compare::LessOrEqual, value)
+            } else if let Some(value) = rest.strip_prefix('>') {
+                (Compare::Greater, value)
+            } else if let Some(value) = rest.strip_prefix('<') {
+                (Compare::Less, value)
+            } else if let Some(value) = rest.strip_prefix('=').or_else(|| rest.strip_prefix(':')) {
+                (Compare::Equal, value)
+            } else {
+                continue;
+            };
+            let value = value.parse().map_err(|_| format!("{:?} is not a number in {:?}", value, s))?;
+            return Ok(Term::Number(field, compare, value));
+        }
+
+        let Some((key, value)) = s.split_once(':') else {
+            return Ok(Term::Text(s.to_string()));
+        };
+        if value.is_empty() {
+            return Err(format!("{:?} needs a value after the colon", s));
+        }
+        match key {
+            "due" => Ok(Term::Due(parse_days(value)?)),
+            "added" => Ok(Term::Added(parse_days(value)?)),
+            "col" => Ok(Term::Column(value.to_string())),
+            "tag" => Ok(Term::Tag(value.to_string())),
+            "deck" => Ok(Term::Deck(value.to_string())),
+            "is" => match value {
+                "new" => Ok(Term::New),
+                "learned" => Ok(Term::Learned),
+                _ => Err(format!("{:?} is not a card state (new, learned)", value)),
+            },
+            // Not a keyword, so it's text like "std::vec"
+            _ => Ok(Term::Text(s.to_string())),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        if tokens.is_empty() {
+            return Err(String::from("the query is empty"));
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(Query { text: s.trim().to_string(), expr }),
+            Some(token) => Err(format!("unexpected {} in the query", token)),
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Minus,
+    Word(String),
+    Quoted(String), // Always text, even "or" or "due:today"
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Open => f.write_str("'('"),
+            Token::Close => f.write_str("')'"),
+            Token::Minus => f.write_str("'-'"),
+            Token::Word(word) => write!(f, "{:?}", word),
+            Token::Quoted(text) => write!(f, "\"{}\"", text),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '"' => {
+                chars.next();
+                let text: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                tokens.push(Token::Quoted(text));
+            }
+            // A leading '-' negates the term right after it
+            '-' => {
+                chars.next();
+                tokens.push(Token::Minus);
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    if s.matches('"').count() % 2 == 1 {
+        return Err(String::from("missing '\"' in the query"));
+    }
+    Ok(tokens)
+}
+
+// Recursive descent over the tokens, "or" binds loosest and terms next to each other are joined with "and"
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.tokens.get(self.pos), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    // True if another term follows, so it gets joined with "and"
+    fn term_follows(&self) -> bool {
+        match self.tokens.get(self.pos) {
+            None | Some(Token::Close) => false,
+            Some(Token::Word(word)) => !word.eq_ignore_ascii_case("or"),
+            Some(_) => true,
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.term_follows() {
+            self.keyword("and");
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Minus) => Ok(Expr::Not(Box::new(self.not()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    return Err(String::from("missing ')' in the query"));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Some(Token::Quoted(text)) => Ok(Expr::Term(Term::Text(text))),
+            Some(Token::Word(word)) if ["and", "or"].iter().any(|k| word.eq_ignore_ascii_case(k)) => {
+                Err(format!("expected a term before {:?}", word))
+            }
+            Some(Token::Word(word)) => Ok(Expr::Term(word.parse()?)),
+            Some(Token::Close) => Err(String::from("unexpected ')' in the query")),
+            None => Err(String::from("the query ends too early")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn matches(query: &str, fields: &[&str], progress: Option<CardProgress>, added: Option<u64>) -> bool {
+        let query: Query = query.parse().unwrap();
+        let (headers, fields, tags) = (strings(&["English", "German"]), strings(fields), strings(&["networking"]));
+        query.matches(&CardInfo { deck: "/decks/words.csv", headers: &headers, fields: &fields, tags: &tags, progress: progress.as_ref(), added, today: 100 })
+    }
+
+    #[test]
+    fn test_terms() {
+        let mut progress = CardProgress::new(90);
+        progress.lapses = 4;
+        progress.due = 100;
+        let card = ["the house", "das Haus"];
+
+        assert!(matches("due:today", &card, Some(progress.clone()), None));
+        assert!(!matches("due:today", &card, None, None));
+        assert!(matches("lapses>3", &card, Some(progress.clone()), None));
+        assert!(!matches("lapses>=5", &card, Some(progress.clone()), None));
+        assert!(matches("reps=0 is:new", &card, None, None));
+        assert!(matches("added:7d", &card, None, Some(95)));
+        assert!(!matches("added:7d", &card, None, Some(90)));
+        assert!(matches("\"HOUSE\"", &card, None, None));
+        assert!(matches("col:german", &card, None, None));
+        assert!(!matches("col:German", &["the house", ""], None, None));
+        assert!(matches("tag:Networking deck:words", &card, None, None));
+
+        // A suspended card is never due
+        progress.suspended = true;
+        assert!(!matches("due:today", &card, Some(progress), None));
+    }
+
+    #[test]
+    fn test_combining_terms() {
+        let card = ["the house", "das Haus"];
+        assert!(matches("house or dog", &card, None, None));
+        assert!(!matches("house dog", &card, None, None));
+        assert!(!matches("house -Haus", &card, None, None));
+        assert!(matches("(dog or house) and not tag:verbs", &card, None, None));
+        assert!(matches("\"the house\" is:new", &card, None, None));
+        // Quoted keywords and words with colons that aren't keywords are plain text
+        assert!(!matches("\"or\"", &card, None, None));
+        assert!(matches("std::vec or house", &card, None, None));
+        assert_eq!("due:today  lapses>3".parse::<Query>().unwrap().to_string(), "due:today  lapses>3");
+    }
+
+    #[test]
+    fn test_invalid_queries() {
+        for query in ["", "due:later", "lapses>many", "is:boring", "(house", "house)", "\"house", "house or", "and house", "due:"] {
+            assert!(query.parse::<Query>().is_err(), "{:?} should not parse", query);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Files inside the data dir
+const RECENT_FILE: &str = "recent.txt";
+const BOOKMARKS_FILE: &str = "bookmarks.txt";
+
+// How many recently used decks are remembered
+pub const RECENT_LIMIT: usize = 10;
+
+// An ordered list of paths stored one per line, most recent first
+#[derive(Debug, Default)]
+pub struct PathList {
+    file: Option<PathBuf>, // No file means the list only lives in memory
+    pub entries: Vec<String>,
+    limit: Option<usize>,
+}
+
+impl PathList {
+    // Load the list from a file, a missing file is just an empty list
+    pub fn load(file: PathBuf, limit: Option<usize>) -> Self {
+        let entries = fs::read_to_string(&file)
+            .map(|content| content.lines().filter(|l| !l.is_empty()).map(|l| l.to_string()).collect())
+            .unwrap_or_default();
+        PathList { file: Some(file), entries, limit }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let Some(ref file) = self.file else {
+            return Ok(());
+        };
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = self.entries.join("\n");
+        content.push('\n');
+        fs::write(file, content)?;
+        Ok(())
+    }
+
+    // Move (or add) an entry to the top of the list
+    pub fn push_front(&mut self, entry: &str) {
+        self.entries.retain(|e| e != entry);
+        self.entries.insert(0, entry.to_string());
+        if let Some(limit) = self.limit {
+            self.entries.truncate(limit);
+        }
+    }
+
+    pub fn remove(&mut self, entry: &str) {
+        self.entries.retain(|e| e != entry);
+    }
+
+    pub fn contains(&self, entry: &str) -> bool {
+        self.entries.iter().any(|e| e == entry)
+    }
+}
+
+// Most recently used decks
+pub fn recent_decks(data_dir: &Path) -> PathList {
+    PathList::load(data_dir.join(RECENT_FILE), Some(RECENT_LIMIT))
+}
+
+// Bookmarked directories of the file browser
+pub fn bookmarks(data_dir: &Path) -> PathList {
+    PathList::load(data_dir.join(BOOKMARKS_FILE), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_front_dedupes_and_limits() {
+        let mut list = PathList { limit: Some(3), ..PathList::default() };
+        list.push_front("/a.csv");
+        list.push_front("/b.csv");
+        list.push_front("/c.csv");
+        list.push_front("/a.csv"); // Already in the list, moves to the top
+        assert_eq!(list.entries, vec!["/a.csv", "/c.csv", "/b.csv"]);
+
+        list.push_front("/d.csv"); // Oldest entry falls off
+        assert_eq!(list.entries, vec!["/d.csv", "/a.csv", "/c.csv"]);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut list = bookmarks(dir.path());
+        assert!(list.entries.is_empty());
+
+        list.push_front("/decks");
+        list.push_front("/shared/decks");
+        list.remove("/decks");
+        list.save().unwrap();
+
+        let loaded = bookmarks(dir.path());
+        assert_eq!(loaded.entries, vec!["/shared/decks"]);
+        assert!(loaded.contains("/shared/decks"));
+    }
+}
@@ -0,0 +1,127 @@
+use csv::{ReaderBuilder, WriterBuilder};
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+
+// Name of the file inside the data dir that every answer is appended to
+const REVIEWS_FILE: &str = "reviews.csv";
+const HEADER: [&str; 5] = ["deck", "card", "day", "correct", "millis"];
+
+// One graded answer. The progress store only keeps the latest state of a card, the log keeps the history for statistics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Review {
+    pub deck: String,
+    pub card: String,
+    pub day: u64,      // Days since the Unix epoch
+    pub correct: bool,
+    pub millis: u64,   // Time from showing the card to grading it
+}
+
+pub fn log_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(REVIEWS_FILE)
+}
+
+// Append a review to the log, creating the data dir and the file with its header if needed
+pub fn append(path: &Path, review: &Review) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let is_new = !path.exists();
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    let mut wtr = WriterBuilder::new().delimiter(b';').has_headers(false).from_writer(file);
+    if is_new {
+        wtr.write_record(HEADER)?;
+    }
+    wtr.write_record(record(review))?;
+    wtr.flush()?;
+    Ok(())
+}
+
+// Fields of a review as written to the log
+fn record(review: &Review) -> [String; 5] {
+    [
+        review.deck.clone(),
+        review.card.clone(),
+        review.day.to_string(),
+        String::from(if review.correct { "1" } else { "0" }),
+        review.millis.to_string(),
+    ]
+}
+
+// Take reviews back out of the log, e.g. when a grade is undone. Each one removes its latest copy,
+// so answers other sessions logged in the meantime stay.
+pub fn remove(path: &Path, removed: &[Review]) -> Result<(), Box<dyn Error>> {
+    let mut reviews = load(path);
+    for review in removed.iter().rev() {
+        if let Some(i) = reviews.iter().rposition(|r| r == review) {
+            reviews.remove(i);
+        }
+    }
+    write(path, &reviews)
+}
+
+// Move the reviews of a deck to a new deck key, e.g. after renaming the file
+pub fn rename_deck(path: &Path, old: &str, new: &str) -> Result<(), Box<dyn Error>> {
+    let mut reviews = load(path);
+    if !reviews.iter().any(|r| r.deck == old) {
+        return Ok(());
+    }
+    for review in reviews.iter_mut().filter(|r| r.deck == old) {
+        review.deck = new.to_string();
+    }
+    write(path, &reviews)
+}
+
+// Replace the whole log with the given reviews
+fn write(path: &Path, reviews: &[Review]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().delimiter(b';').from_path(path)?;
+    wtr.write_record(HEADER)?;
+    for review in reviews {
+        wtr.write_record(record(review))?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+// Every logged review, oldest first. A missing file means no reviews yet, broken lines are skipped
+pub fn load(path: &Path) -> Vec<Review> {
+    let Ok(mut rdr) = ReaderBuilder::new().delimiter(b';').from_path(path) else {
+        return Vec::new();
+    };
+    rdr.records()
+        .filter_map(|r| r.ok())
+        .filter_map(|record| {
+            Some(Review {
+                deck: record.get(0)?.to_string(),
+                card: record.get(1)?.to_string(),
+                day: record.get(2)?.parse().ok()?,
+                correct: record.get(3)? == "1",
+                millis: record.get(4)?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = log_path(&dir.path().join("data"));
+        let review = |card: &str, correct| Review { deck: String::from("deck"), card: card.to_string(), day: 10, correct, millis: 1500 };
+
+        assert!(load(&path).is_empty());
+        append(&path, &review("La maison", true)).unwrap();
+        append(&path, &review("Le chien; the dog", false)).unwrap();
+
+        assert_eq!(load(&path), vec![review("La maison", true), review("Le chien; the dog", false)]);
+
+        // Another session logged an answer after ours
+        append(&path, &review("Le chat", true)).unwrap();
+        remove(&path, &[review("Le chien; the dog", false)]).unwrap();
+        assert_eq!(load(&path), vec![review("La maison", true), review("Le chat", true)]);
+    }
+}
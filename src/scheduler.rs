@@ -0,0 +1,101 @@
+// Cards with an interval of at least this many days count as learned
+pub const LEARNED_INTERVAL: u32 = 21;
+// Cards forgotten this many times are leeches unless configured otherwise
+pub const LEECH_LAPSES: u32 = 8;
+
+// Learning state of a single card, days are counted since the Unix epoch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardProgress {
+    pub interval: u32,    // Days until the card should be seen again
+    pub due: u64,         // Day the card is due
+    pub reps: u32,        // Number of reviews
+    pub lapses: u32,      // Number of times the card was forgotten
+    pub last_review: u64, // Day of the last review
+    pub suspended: bool,  // Left out of sessions until unsuspended, e.g. a leech waiting to be rewritten
+}
+
+impl CardProgress {
+    // State of a card that is about to be reviewed for the first time
+    pub fn new(today: u64) -> Self {
+        CardProgress { interval: 0, due: today, reps: 0, lapses: 0, last_review: today, suspended: false }
+    }
+
+    // Simple doubling schedule: a correct answer doubles the interval, a miss starts over
+    pub fn review(&mut self, correct: bool, today: u64) {
+        if correct {
+            self.interval = (self.interval * 2).max(1);
+        } else {
+            self.interval = 0;
+            self.lapses += 1;
+        }
+        self.reps += 1;
+        self.due = today + self.interval as u64;
+        self.last_review = today;
+    }
+
+    // Suspended cards are never due
+    pub fn is_due(&self, today: u64) -> bool {
+        self.due <= today && !self.suspended
+    }
+
+    pub fn is_learned(&self) -> bool {
+        self.interval >= LEARNED_INTERVAL
+    }
+
+    // A card that keeps getting forgotten, a threshold of 0 never flags one
+    pub fn is_leech(&self, threshold: u32) -> bool {
+        threshold > 0 && self.lapses >= threshold
+    }
+}
+
+// Rough difficulty of a card for ordering a session, lower buckets are harder:
+// 0 missed at the last review, 1 never studied, 2 still learning, 3 learned
+pub fn difficulty_bucket(progress: Option<&CardProgress>) -> usize {
+    match progress {
+        None => 1,
+        Some(p) if p.reps > 0 && p.interval == 0 => 0,
+        Some(p) if p.is_learned() => 3,
+        Some(_) => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review_schedule() {
+        let mut progress = CardProgress::new(100);
+
+        // Correct answers double the interval
+        progress.review(true, 100);
+        assert_eq!(progress.interval, 1);
+        assert_eq!(progress.due, 101);
+        progress.review(true, 101);
+        assert_eq!(progress.interval, 2);
+        assert_eq!(progress.due, 103);
+
+        // A miss resets the interval and counts a lapse
+        progress.review(false, 103);
+        assert_eq!(progress.interval, 0);
+        assert_eq!(progress.lapses, 1);
+        assert_eq!(progress.reps, 3);
+        assert!(progress.is_due(103));
+        assert!(!progress.is_learned());
+        assert!(progress.is_leech(1));
+        assert!(!progress.is_leech(2));
+        assert!(!progress.is_leech(0));
+    }
+
+    #[test]
+    fn test_difficulty_bucket() {
+        let mut progress = CardProgress::new(0);
+        assert_eq!(difficulty_bucket(None), 1);
+        progress.review(false, 0);
+        assert_eq!(difficulty_bucket(Some(&progress)), 0);
+        progress.review(true, 0);
+        assert_eq!(difficulty_bucket(Some(&progress)), 2);
+        progress.interval = LEARNED_INTERVAL;
+        assert_eq!(difficulty_bucket(Some(&progress)), 3);
+    }
+}
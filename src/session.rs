@@ -0,0 +1,1351 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use std::str::FromStr;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use crate::added::AddedStore;
+use crate::file_handler::{self, DeckMeta};
+use crate::progress::{self, ProgressStore};
+use crate::query::{CardInfo, Query};
+use crate::reviews::{self, Review};
+use crate::scheduler::{self, CardProgress, LEECH_LAPSES};
+use crate::tags::{self, TagFilter};
+
+// Cards between a missed card and its next try unless configured otherwise
+pub const RETRY_AFTER: usize = 3;
+
+// How the cards of a deck are ordered before a session takes the first ones
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ShuffleMode {
+    None,    // Keep the order of the file
+    #[default]
+    Full,    // Shuffle the whole deck
+    Buckets, // Hardest cards first, shuffled within each difficulty bucket
+}
+
+impl FromStr for ShuffleMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "none" => Ok(ShuffleMode::None),
+            "full" => Ok(ShuffleMode::Full),
+            "buckets" => Ok(ShuffleMode::Buckets),
+            _ => Err(format!("{:?} is not a shuffle mode (none, full, buckets)", value)),
+        }
+    }
+}
+
+impl fmt::Display for ShuffleMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ShuffleMode::None => "none",
+            ShuffleMode::Full => "full",
+            ShuffleMode::Buckets => "buckets",
+        })
+    }
+}
+
+// Put the drawn cards in session order, `bucket` says how hard a card is
+fn order_by<T, R: Rng>(items: &mut [T], mode: ShuffleMode, rng: &mut R, bucket: impl Fn(&T) -> usize) {
+    match mode {
+        ShuffleMode::None => {}
+        ShuffleMode::Full => items.shuffle(rng),
+        ShuffleMode::Buckets => {
+            // Shuffle first, the stable sort keeps that order inside each bucket
+            items.shuffle(rng);
+            items.sort_by_key(bucket);
+        }
+    }
+}
+
+// Cards count as added on the first day a session reads them from their deck, for queries like "added:7d"
+fn note_added(added: &mut AddedStore, source: &Source, cards: &[Drawn]) {
+    let today = progress::today();
+    for question in cards.iter().filter_map(|d| d.card.first()) {
+        added.note(&source.deck, question, today);
+    }
+}
+
+// Saving the added days can fail without stopping the session, it's left in the warnings
+fn save_added(added: &AddedStore) -> Option<String> {
+    added.save().err().map(|err| format!("Error saving added cards: {}", err))
+}
+
+// Suspended cards sit out of sessions until they are unsuspended on the leech screen
+fn is_suspended(store: &ProgressStore, deck: &str, card: &[String]) -> bool {
+    card.first().and_then(|question| store.get(deck, question)).map(|p| p.suspended).unwrap_or(false)
+}
+
+// A deck the cards of a session come from. Cards of a custom study session can come from several.
+#[derive(Debug, Clone, PartialEq)]
+struct Source {
+    deck: String,               // Key of the deck in the progress store, empty for cards without a deck file
+    headers: Vec<String>,       // Column names the cards of this deck are shown with
+    columns: Vec<usize>,        // Column of the deck file each card column came from
+    tags_column: Option<usize>, // Column of the deck file holding the tags
+    meta: DeckMeta,             // Name and settings of the deck
+}
+
+pub const NOT_EDITABLE: &str = "Cards of JSON, TOML, YAML and Markdown decks can't be edited here, change them in the deck file";
+
+// A card picked for a session before it's known which ones make it in
+struct Drawn {
+    card: Vec<String>,
+    tags: String,
+    source: usize,
+}
+
+// Read a deck for a session with its columns in the given order, or the deck's own direction without one.
+// The tags are split off each card. The cards point to source 0 until they are put together with the cards of other decks.
+fn read_deck(file_path: &str, columns: Option<&[usize]>) -> Result<(Source, Vec<Drawn>), Box<dyn Error>> {
+    let meta = file_handler::read_metadata(file_path)?;
+    let columns = columns.or(meta.direction.as_deref());
+    let (headers, cards) = file_handler::read_csv_with_headers(file_path)?;
+    let tags_column = tags::tags_column(&headers);
+    let card_tags: Vec<String> = cards.iter().map(|card| tags_column.and_then(|c| card.get(c)).cloned().unwrap_or_default()).collect();
+
+    // The tags column is shown next to the card instead of as one of its answers
+    let file_columns = match columns {
+        Some(columns) => columns.to_vec(),
+        None => (0..headers.len()).filter(|&c| Some(c) != tags_column).collect(),
+    };
+    let (headers, cards) = if columns.is_some() || tags_column.is_some() {
+        file_handler::apply_direction(headers, cards, &file_columns)?
+    } else {
+        (headers, cards)
+    };
+
+    let source = Source { deck: progress::deck_key(Path::new(file_path)), headers, columns: file_columns, tags_column, meta };
+    Ok((source, cards.into_iter().zip(card_tags).map(|(card, tags)| Drawn { card, tags, source: 0 }).collect()))
+}
+
+// The cards drawn for one round of studying and the progress they are graded into.
+// Frontends (the full-screen game, the plain line mode) only decide how cards are shown.
+pub struct Session {
+    pub headers: Vec<String>,    // Column names, the first one is the question. Cards from other decks have their own, see card_headers
+    pub cards: Vec<Vec<String>>, // Rows drawn from the deck
+    sources: Vec<Source>,        // Decks the cards come from
+    origin: Vec<usize>,          // Source of each card
+    pub tags: Vec<String>,       // Tags of each card as written in the deck, empty without a tags column
+    pub current: usize,          // Index of the card being studied, cards.len() once the queue is empty
+    queue: VecDeque<usize>,      // Cards still to study, the current one first
+    shown: Vec<bool>,            // Cards that were shown and left the queue at least once
+    history: Vec<usize>,         // Cards in the order they were shown before the current one, for going back
+    pub retry_after: usize,      // A missed card comes back after this many other cards, 0 doesn't bring it back
+    pub leech_lapses: u32,       // Lapses that make a card a leech, 0 turns leech detection off
+    pub suspend_leeches: bool,   // Suspend a card once it becomes a leech instead of bringing it back
+    pub leeches: Vec<String>,    // Cards that became leeches in this session
+    pub deck: String,            // Key of the deck in the progress store, empty if the cards come from several decks
+    pub progress: ProgressStore,
+    pub reviews: Vec<Review>,    // Answers graded in this session
+    pub warnings: Vec<String>,   // Files that couldn't be read or saved, for the caller to show
+    log: Option<PathBuf>,        // Review log the answers are appended to, None keeps them in memory
+    shown_at: Instant,           // When the current card was shown, for the answer time
+    started: Instant,
+    undo: Vec<Snapshot>,         // State before each grade, skip and edit, latest last
+    redo: Vec<Snapshot>,         // Undone states, cleared by the next new action
+}
+
+// Everything an action can change, so undoing it puts back the progress, the log and the deck file too
+#[derive(Debug, Clone)]
+struct Snapshot {
+    current: usize,
+    queue: VecDeque<usize>,
+    shown: Vec<bool>,
+    history: Vec<usize>,
+    reviews: Vec<Review>,
+    leeches: Vec<String>,
+    cards: Vec<Vec<String>>,
+    tags: Vec<String>,
+    progress: Vec<(String, String, Option<CardProgress>)>, // Progress of each card by its deck and question
+}
+
+// Where a card stands in the session, for the card list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardStatus {
+    Unseen,
+    Seen, // Shown but skipped
+    Correct,
+    Failed,
+}
+
+impl fmt::Display for CardStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            CardStatus::Unseen => "not seen yet",
+            CardStatus::Seen => "seen",
+            CardStatus::Correct => "correct",
+            CardStatus::Failed => "failed",
+        })
+    }
+}
+
+// What the end-of-session screen shows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub seen: usize,          // Cards gone through, graded or skipped
+    pub graded: usize,
+    pub correct: usize,
+    pub hardest: Vec<String>, // Missed cards first, then the slowest answers
+    pub leeches: Vec<String>, // Cards that became leeches
+    pub duration: Duration,
+}
+
+impl Summary {
+    // Number of hardest cards listed
+    const HARDEST: usize = 3;
+
+    // Share of correct grades in percent, None when nothing was graded
+    pub fn correct_rate(&self) -> Option<f64> {
+        (self.graded > 0).then(|| self.correct as f64 * 100.0 / self.graded as f64)
+    }
+}
+
+impl Session {
+    // A session that isn't backed by a deck file, progress is kept in memory only
+    pub fn new(headers: Vec<String>, cards: Vec<Vec<String>>) -> Self {
+        Session {
+            queue: (0..cards.len()).collect(),
+            shown: vec![false; cards.len()],
+            history: Vec::new(),
+            sources: vec![Source { deck: String::new(), headers: headers.clone(), columns: (0..headers.len()).collect(), tags_column: None, meta: DeckMeta::default() }],
+            origin: vec![0; cards.len()],
+            tags: vec![String::new(); cards.len()],
+            headers,
+            cards,
+            current: 0,
+            retry_after: RETRY_AFTER,
+            leech_lapses: LEECH_LAPSES,
+            suspend_leeches: false,
+            leeches: Vec::new(),
+            deck: String::new(),
+            progress: ProgressStore::default(),
+            reviews: Vec::new(),
+            warnings: Vec::new(),
+            log: None,
+            shown_at: Instant::now(),
+            started: Instant::now(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    // Draw `size` cards from a deck in the given shuffle mode, optionally only the ones whose tags match a filter
+    // and with its columns reordered. The same seeded RNG and progress give the same session.
+    // Progress and the review log are read from and written to the data dir.
+    pub fn load<R: Rng>(data_dir: &Path, file_path: &str, size: usize, columns: Option<&[usize]>, filter: Option<&TagFilter>, shuffle: ShuffleMode, rng: &mut R) -> Result<Self, Box<dyn Error>> {
+        let store = ProgressStore::load(data_dir);
+        let (source, cards) = read_deck(file_path, columns)?;
+        if filter.is_some() && source.tags_column.is_none() {
+            return Err("The deck has no tags column to filter by".into());
+        }
+
+        let mut added = AddedStore::load(data_dir);
+        note_added(&mut added, &source, &cards);
+        let warnings = save_added(&added).into_iter().collect();
+
+        let drawn = cards.into_iter()
+            .filter(|d| !is_suspended(&store, &source.deck, &d.card))
+            .filter(|d| filter.map(|filter| filter.matches(&tags::parse_tags(&d.tags))).unwrap_or(true))
+            .collect();
+        Ok(Session { warnings, ..Session::drawn(vec![source], drawn, size, shuffle, store, data_dir, rng) })
+    }
+
+    // Draw `size` cards from several decks together, as if they were one deck. Each card keeps its own headers.
+    // With a tag filter, decks without a tags column are left out and named in the warnings.
+    pub fn load_decks<R: Rng>(data_dir: &Path, decks: &[String], size: usize, filter: Option<&TagFilter>, shuffle: ShuffleMode, rng: &mut R) -> Result<Self, Box<dyn Error>> {
+        if let [deck] = decks {
+            return Session::load(data_dir, deck, size, None, filter, shuffle, rng);
+        }
+        let store = ProgressStore::load(data_dir);
+        let mut added = AddedStore::load(data_dir);
+        let mut sources = Vec::new();
+        let mut drawn = Vec::new();
+        let mut warnings = Vec::new();
+        for deck in decks {
+            let (source, cards) = read_deck(deck, None).map_err(|err| format!("{}: {}", deck, err))?;
+            note_added(&mut added, &source, &cards);
+            if filter.is_some() && source.tags_column.is_none() {
+                warnings.push(format!("{} has no tags column to filter by", deck));
+                continue;
+            }
+            drawn.extend(cards.into_iter()
+                .filter(|d| !is_suspended(&store, &source.deck, &d.card))
+                .filter(|d| filter.map(|filter| filter.matches(&tags::parse_tags(&d.tags))).unwrap_or(true))
+                .map(|d| Drawn { source: sources.len(), ..d }));
+            sources.push(source);
+        }
+        warnings.extend(save_added(&added));
+        Ok(Session { warnings, ..Session::drawn(sources, drawn, size, shuffle, store, data_dir, rng) })
+    }
+
+    // Draw `size` cards matching a query from several decks, e.g. everything due today across the library.
+    // Decks that can't be read are left out.
+    pub fn query<R: Rng>(data_dir: &Path, decks: &[String], query: &Query, size: usize, shuffle: ShuffleMode, rng: &mut R) -> Self {
+        let store = ProgressStore::load(data_dir);
+        let mut added = AddedStore::load(data_dir);
+        let today = progress::today();
+        let mut sources = Vec::new();
+        let mut drawn = Vec::new();
+        let mut warnings = Vec::new();
+        for deck in decks {
+            let (source, cards) = match read_deck(deck, None) {
+                Ok(deck) => deck,
+                Err(err) => {
+                    warnings.push(format!("Error reading {}: {}", deck, err));
+                    continue;
+                }
+            };
+            note_added(&mut added, &source, &cards);
+            for card in cards {
+                let Some(question) = card.card.first().filter(|_| !is_suspended(&store, &source.deck, &card.card)) else {
+                    continue;
+                };
+                let info = CardInfo {
+                    deck: &source.deck,
+                    headers: &source.headers,
+                    fields: &card.card,
+                    tags: &tags::parse_tags(&card.tags),
+                    progress: store.get(&source.deck, question),
+                    added: added.get(&source.deck, question),
+                    today,
+                };
+                if query.matches(&info) {
+                    drawn.push(Drawn { source: sources.len(), ..card });
+                }
+            }
+            sources.push(source);
+        }
+        warnings.extend(save_added(&added));
+
+        Session { warnings, ..Session::drawn(sources, drawn, size, shuffle, store, data_dir, rng) }
+    }
+
+    // A session on the first `size` of the drawn cards in session order, graded into the store and the log of the data dir
+    fn drawn<R: Rng>(sources: Vec<Source>, mut drawn: Vec<Drawn>, size: usize, shuffle: ShuffleMode, store: ProgressStore, data_dir: &Path, rng: &mut R) -> Self {
+        order_by(&mut drawn, shuffle, rng, |d| {
+            scheduler::difficulty_bucket(d.card.first().and_then(|q| store.get(&sources[d.source].deck, q)))
+        });
+        drawn.truncate(size);
+
+        let headers = sources.first().map(|source| source.headers.clone()).unwrap_or_default();
+        // Cards from several decks don't share one, each grade goes to the deck of its card
+        let deck = match sources.as_slice() {
+            [source] => source.deck.clone(),
+            _ => String::new(),
+        };
+        let origin = drawn.iter().map(|d| d.source).collect();
+        let tags = drawn.iter().map(|d| d.tags.clone()).collect();
+        let cards = drawn.into_iter().map(|d| d.card).collect();
+        Session {
+            sources,
+            origin,
+            tags,
+            log: Some(reviews::log_path(data_dir)),
+            progress: store,
+            deck,
+            ..Session::new(headers, cards)
+        }
+    }
+
+    fn source(&self, index: usize) -> Option<&Source> {
+        self.origin.get(index).and_then(|&source| self.sources.get(source))
+    }
+
+    // Number of decks the cards come from
+    pub fn deck_count(&self) -> usize {
+        self.sources.len()
+    }
+
+    // Key of the deck a card comes from
+    pub fn card_deck(&self, index: usize) -> &str {
+        self.source(index).map(|source| source.deck.as_str()).unwrap_or("")
+    }
+
+    // Name and settings of the deck a card comes from
+    pub fn card_meta(&self, index: usize) -> Option<&DeckMeta> {
+        self.source(index).map(|source| &source.meta)
+    }
+
+    // Name of the deck a card comes from: the one the deck gives itself, or its file name
+    pub fn deck_name(&self, index: usize) -> Option<String> {
+        let source = self.source(index).filter(|source| !source.deck.is_empty())?;
+        source.meta.name.clone().or_else(|| Path::new(&source.deck).file_name().map(|name| name.to_string_lossy().to_string()))
+    }
+
+    // Column names of a card with the language of each column if its deck says, e.g. "French (fr)"
+    pub fn card_labels(&self, index: usize) -> Vec<String> {
+        let meta = self.card_meta(index);
+        self.card_headers(index).iter().map(|header| match meta.and_then(|meta| meta.language(header)) {
+            Some(language) => format!("{} ({})", header, language),
+            None => header.clone(),
+        }).collect()
+    }
+
+    // Apply the settings from the config, the ones a single deck sets for itself win
+    pub fn configure(&mut self, retry_after: usize, leech_lapses: u32, suspend_leeches: bool) {
+        let meta = match self.sources.as_slice() {
+            [source] => source.meta.clone(),
+            _ => DeckMeta::default(),
+        };
+        self.retry_after = meta.retry_after.unwrap_or(retry_after);
+        self.leech_lapses = meta.leech_lapses.unwrap_or(leech_lapses);
+        self.suspend_leeches = meta.suspend_leeches.unwrap_or(suspend_leeches);
+    }
+
+    // Column names of a card, cards from different decks can have different ones
+    pub fn card_headers(&self, index: usize) -> &[String] {
+        self.source(index).map(|source| source.headers.as_slice()).unwrap_or(&self.headers)
+    }
+
+    pub fn current_card(&self) -> Option<&Vec<String>> {
+        self.cards.get(self.current)
+    }
+
+    // Move on to the next card, the session is finished once the queue is empty
+    pub fn advance(&mut self) {
+        if !self.queue.is_empty() {
+            self.checkpoint();
+        }
+        self.next_card(false);
+    }
+
+    // Take the current card off the queue, a missed one goes back in a few cards later
+    fn next_card(&mut self, missed: bool) {
+        if let Some(card) = self.queue.pop_front() {
+            self.shown[card] = true;
+            self.history.push(card);
+            if missed && self.retry_after > 0 {
+                let at = self.retry_after.min(self.queue.len());
+                self.queue.insert(at, card);
+            }
+        }
+        self.current = self.queue.front().copied().unwrap_or(self.cards.len());
+        self.shown_at = Instant::now();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.cards.is_empty() && self.queue.is_empty()
+    }
+
+    // Cards left in the queue, including the current one and missed ones coming back
+    pub fn remaining(&self) -> usize {
+        self.queue.len()
+    }
+
+    // True when the current card was shown before, a missed card coming back or one gone back to
+    pub fn is_repeat(&self) -> bool {
+        self.shown.get(self.current).copied().unwrap_or(false)
+    }
+
+    // Show the card with the given index next, wherever it is in the queue or if it was done already
+    pub fn go_to(&mut self, index: usize) -> bool {
+        if index >= self.cards.len() || (index == self.current && !self.is_finished()) {
+            return false;
+        }
+        self.checkpoint();
+        if self.current < self.cards.len() {
+            self.history.push(self.current);
+        }
+        self.show(index);
+        true
+    }
+
+    // Step back to the card shown before the current one, also when a missed card came back in between
+    pub fn previous(&mut self) -> bool {
+        let Some(position) = self.history.iter().rposition(|&card| card != self.current) else {
+            return false;
+        };
+        self.checkpoint();
+        let index = self.history[position];
+        self.history.truncate(position);
+        self.show(index);
+        true
+    }
+
+    // Put a card at the front of the queue and make it the current one
+    fn show(&mut self, index: usize) {
+        self.queue.retain(|&card| card != index);
+        self.queue.push_front(index);
+        self.current = index;
+        self.shown_at = Instant::now();
+    }
+
+    // How a card did in this session, its latest grade counts. Decks studied together can share a question.
+    pub fn card_status(&self, index: usize) -> CardStatus {
+        let question = self.cards.get(index).and_then(|card| card.first());
+        let deck = self.card_deck(index);
+        match self.reviews.iter().rev().find(|review| review.deck == deck && Some(&review.card) == question) {
+            Some(review) if review.correct => CardStatus::Correct,
+            Some(_) => CardStatus::Failed,
+            None if self.shown.get(index).copied().unwrap_or(false) => CardStatus::Seen,
+            None => CardStatus::Unseen,
+        }
+    }
+
+    // Cards that were answered wrong at least once in this session, in session order, without suspended ones
+    pub fn missed_cards(&self) -> Vec<Vec<String>> {
+        self.missed().map(|i| self.cards[i].clone()).collect()
+    }
+
+    fn missed(&self) -> impl Iterator<Item = usize> + '_ {
+        let missed = move |i: usize| {
+            let deck = self.card_deck(i);
+            self.cards[i].first().map(|question| {
+                self.reviews.iter().any(|r| !r.correct && r.deck == deck && &r.card == question)
+                    && !is_suspended(&self.progress, deck, &self.cards[i])
+            }).unwrap_or(false)
+        };
+        (0..self.cards.len()).filter(move |&i| missed(i))
+    }
+
+    // A new session with only the missed cards, grades keep going into the same progress and log
+    pub fn retry_missed(self) -> Session {
+        let missed: Vec<usize> = self.missed().collect();
+        let origin = missed.iter().map(|&i| self.origin[i]).collect();
+        let tags = missed.iter().map(|&i| self.tags[i].clone()).collect();
+        let cards = missed.iter().map(|&i| self.cards[i].clone()).collect();
+        Session { origin, tags, ..self.with_cards(cards) }
+    }
+
+    // Tags of a card, split into single tags
+    pub fn card_tags(&self, index: usize) -> Vec<String> {
+        self.tags.get(index).map(|text| tags::parse_tags(text)).unwrap_or_default()
+    }
+
+    // True if a card comes from a deck with a tags column, so its tags can be edited
+    pub fn has_tags(&self, index: usize) -> bool {
+        self.source(index).map(|source| source.tags_column.is_some()).unwrap_or(false)
+    }
+
+    // False for cards of JSON, TOML, YAML and Markdown decks, those can only be changed in their file
+    pub fn is_editable(&self, index: usize) -> bool {
+        !file_handler::is_structured_deck(Path::new(self.card_deck(index)))
+    }
+
+    // A fresh session on other cards of the same deck, their tags are left empty
+    pub fn with_cards(self, cards: Vec<Vec<String>>) -> Session {
+        Session {
+            sources: self.sources,
+            deck: self.deck,
+            progress: self.progress,
+            log: self.log,
+            retry_after: self.retry_after,
+            leech_lapses: self.leech_lapses,
+            suspend_leeches: self.suspend_leeches,
+            ..Session::new(self.headers, cards)
+        }
+    }
+
+    // Replace the fields of a card and optionally its tags, in the deck file too if there is one.
+    // A changed question keeps its progress.
+    pub fn edit_card(&mut self, index: usize, fields: Vec<String>, tags: Option<String>) -> Result<(), Box<dyn Error>> {
+        let Some(old) = self.cards.get(index).cloned() else {
+            return Err("No such card".into());
+        };
+        if !self.is_editable(index) {
+            return Err(NOT_EDITABLE.into());
+        }
+        let tags = tags.unwrap_or_else(|| self.tags[index].clone());
+        let deck = self.card_deck(index).to_string();
+        let before = self.snapshot();
+
+        // The progress is saved first and the deck file written after it. Whatever was changed
+        // when one of them fails is put back, so the session still matches the files.
+        let renamed = match (old.first(), fields.first()) {
+            (Some(old_question), Some(new_question)) if old_question != new_question => vec![old_question.clone(), new_question.clone()],
+            _ => Vec::new(),
+        };
+        let kept: Vec<(String, Option<CardProgress>)> = renamed.iter().map(|question| (question.clone(), self.progress.get(&deck, question).cloned())).collect();
+        if let [old_question, new_question] = renamed.as_slice() {
+            self.progress.rename_card(&deck, old_question, new_question);
+            if let Err(err) = self.progress.save() {
+                self.put_back(&deck, kept);
+                return Err(err);
+            }
+        }
+        if !deck.is_empty() {
+            let (columns, old) = self.file_row(index, &old, &self.tags[index]);
+            let (_, new) = self.file_row(index, &fields, &tags);
+            if let Err(err) = file_handler::replace_row(&deck, &columns, &old, &new) {
+                if !kept.is_empty() {
+                    self.put_back(&deck, kept);
+                    if let Err(err) = self.progress.save() {
+                        self.warnings.push(format!("Error saving progress: {}", err));
+                    }
+                }
+                return Err(err);
+            }
+        }
+
+        self.undo.push(before);
+        self.redo.clear();
+        self.cards[index] = fields;
+        self.tags[index] = tags;
+        Ok(())
+    }
+
+    // Put back the progress of cards as it was before a failed edit
+    fn put_back(&mut self, deck: &str, kept: Vec<(String, Option<CardProgress>)>) {
+        for (question, progress) in kept {
+            self.progress.restore(deck, &question, progress);
+        }
+    }
+
+    // The deck file columns of the card at `index` and the given fields in them,
+    // the tags included if its deck has a tags column
+    fn file_row(&self, index: usize, card: &[String], tags: &str) -> (Vec<usize>, Vec<String>) {
+        let source = self.source(index);
+        let mut columns = source.map(|source| source.columns.clone()).unwrap_or_default();
+        let mut fields = card.to_vec();
+        if let Some(column) = source.and_then(|source| source.tags_column) {
+            columns.push(column);
+            fields.push(tags.to_string());
+        }
+        (columns, fields)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            current: self.current,
+            queue: self.queue.clone(),
+            shown: self.shown.clone(),
+            history: self.history.clone(),
+            reviews: self.reviews.clone(),
+            leeches: self.leeches.clone(),
+            cards: self.cards.clone(),
+            tags: self.tags.clone(),
+            progress: self.cards.iter().enumerate()
+                .filter_map(|(i, card)| card.first().map(|question| (self.card_deck(i), question)))
+                .map(|(deck, question)| (deck.to_string(), question.clone(), self.progress.get(deck, question).cloned()))
+                .collect(),
+        }
+    }
+
+    // Remember the state before a new action, which also makes the undone ones unreachable
+    fn checkpoint(&mut self) {
+        let snapshot = self.snapshot();
+        self.undo.push(snapshot);
+        self.redo.clear();
+    }
+
+    // Go back to before the last grade, skip or edit. Returns false if there is nothing to undo,
+    // or if an edited card can't be written back to its deck, which is left in the warnings.
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo.last().cloned() else {
+            return false;
+        };
+        let now = self.snapshot();
+        if let Err(err) = self.restore(snapshot) {
+            self.warnings.push(format!("Error restoring card: {}", err));
+            return false;
+        }
+        self.undo.pop();
+        self.redo.push(now);
+        true
+    }
+
+    // Do the last undone action again
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo.last().cloned() else {
+            return false;
+        };
+        let now = self.snapshot();
+        if let Err(err) = self.restore(snapshot) {
+            self.warnings.push(format!("Error restoring card: {}", err));
+            return false;
+        }
+        self.redo.pop();
+        self.undo.push(now);
+        true
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) -> Result<(), Box<dyn Error>> {
+        // Edited cards go back into the deck file first, if one can't be written the session stays as it is
+        let edited: Vec<usize> = (0..self.cards.len().min(snapshot.cards.len()))
+            .filter(|&i| !self.card_deck(i).is_empty() && (self.cards[i] != snapshot.cards[i] || self.tags[i] != snapshot.tags[i]))
+            .collect();
+        let rows = |i: usize| {
+            let (columns, now) = self.file_row(i, &self.cards[i], &self.tags[i]);
+            let (_, then) = self.file_row(i, &snapshot.cards[i], &snapshot.tags[i]);
+            (self.card_deck(i), columns, now, then)
+        };
+        for (written, &i) in edited.iter().enumerate() {
+            let (deck, columns, now, then) = rows(i);
+            if let Err(err) = file_handler::replace_row(deck, &columns, &now, &then) {
+                for &i in edited[..written].iter().rev() {
+                    let (deck, columns, now, then) = rows(i);
+                    let _ = file_handler::replace_row(deck, &columns, &then, &now);
+                }
+                return Err(err);
+            }
+        }
+
+        // Forget the progress under the current questions first, an edit may have renamed one
+        let current: Vec<(String, String)> = self.cards.iter().enumerate()
+            .filter_map(|(i, card)| card.first().map(|question| (self.card_deck(i).to_string(), question.clone())))
+            .collect();
+        for (deck, question) in current {
+            self.progress.restore(&deck, &question, None);
+        }
+        for (deck, question, progress) in snapshot.progress {
+            self.progress.restore(&deck, &question, progress);
+        }
+        if let Err(err) = self.progress.save() {
+            self.warnings.push(format!("Error saving progress: {}", err));
+        }
+
+        // Reviews are only ever added, so the log either loses the newer ones of this session or gets them back
+        if let Some(ref log) = self.log {
+            let result = if snapshot.reviews.len() < self.reviews.len() {
+                reviews::remove(log, &self.reviews[snapshot.reviews.len()..])
+            } else {
+                snapshot.reviews[self.reviews.len()..].iter().try_for_each(|review| reviews::append(log, review))
+            };
+            if let Err(err) = result {
+                self.warnings.push(format!("Error saving review log: {}", err));
+            }
+        }
+
+        self.current = snapshot.current;
+        self.queue = snapshot.queue;
+        self.shown = snapshot.shown;
+        self.history = snapshot.history;
+        self.reviews = snapshot.reviews;
+        self.leeches = snapshot.leeches;
+        self.cards = snapshot.cards;
+        self.tags = snapshot.tags;
+        self.shown_at = Instant::now();
+        Ok(())
+    }
+
+    pub fn summary(&self) -> Summary {
+        let mut hardest: Vec<&Review> = self.reviews.iter().collect();
+        hardest.sort_by_key(|r| (r.correct, std::cmp::Reverse(r.millis)));
+        let mut names: Vec<String> = Vec::new();
+        for review in hardest {
+            if names.len() < Summary::HARDEST && !names.contains(&review.card) {
+                names.push(review.card.clone());
+            }
+        }
+
+        Summary {
+            seen: self.shown.iter().filter(|shown| **shown).count(),
+            graded: self.reviews.len(),
+            correct: self.reviews.iter().filter(|r| r.correct).count(),
+            hardest: names,
+            leeches: self.leeches.clone(),
+            duration: self.started.elapsed(),
+        }
+    }
+
+    // Record whether the current card was known, save the progress and move on.
+    // A missed card is queued again, unless it just became a leech and leeches get suspended.
+    pub fn grade(&mut self, correct: bool) {
+        let Some(question) = self.current_card().and_then(|card| card.first()).cloned() else {
+            return;
+        };
+        let deck = self.card_deck(self.current).to_string();
+        let review = Review {
+            deck: deck.clone(),
+            card: question,
+            day: progress::today(),
+            correct,
+            millis: self.shown_at.elapsed().as_millis() as u64,
+        };
+        self.checkpoint();
+        self.progress.record(&deck, &review.card, correct, review.day);
+        let mut suspended = false;
+        let lapses = self.progress.get(&deck, &review.card).map(|p| p.lapses).unwrap_or(0);
+        if !correct && self.leech_lapses > 0 && lapses == self.leech_lapses {
+            self.leeches.push(review.card.clone());
+            suspended = self.suspend_leeches && self.progress.set_suspended(&deck, &review.card, true);
+        }
+        if let Err(err) = self.progress.save() {
+            self.warnings.push(format!("Error saving progress: {}", err));
+        }
+        if let Some(ref log) = self.log {
+            if let Err(err) = reviews::append(log, &review) {
+                self.warnings.push(format!("Error saving review log: {}", err));
+            }
+        }
+        self.reviews.push(review);
+        self.next_card(!correct && !suspended);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        Session::new(
+            vec![String::from("Question"), String::from("Answer")],
+            vec![
+                vec![String::from("What is Rust?"), String::from("A language")],
+                vec![String::from("What is Cargo?"), String::from("A package manager")],
+            ],
+        )
+    }
+
+    #[test]
+    fn test_grade_records_and_advances() {
+        let mut session = session();
+        session.grade(false);
+
+        assert_eq!(session.progress.get("", "What is Rust?").unwrap().lapses, 1);
+        assert_eq!(session.current, 1);
+        assert_eq!(session.reviews.len(), 1);
+        assert!(!session.reviews[0].correct);
+    }
+
+    #[test]
+    fn test_save_errors_become_warnings() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        // The progress and the log would live inside a regular file
+        let mut session = Session {
+            progress: ProgressStore::load(&file.path().join("data")),
+            log: Some(file.path().join("reviews.csv")),
+            ..session()
+        };
+        session.grade(true);
+        assert_eq!(session.current, 1, "The session goes on");
+        assert_eq!(session.warnings.len(), 2);
+        assert!(session.warnings[0].starts_with("Error saving progress"));
+        assert!(session.warnings[1].starts_with("Error saving review log"));
+    }
+
+    #[test]
+    fn test_load_success() {
+        use std::io::Write;
+        let dir = tempfile::tempdir().unwrap();
+        // Create a temporary CSV file with headers and data
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            temp_file,
+            "Question;Answer\nWhat is Rust?;A systems programming language.\nWhat is Cargo?;Rust's package manager."
+        )
+        .unwrap();
+
+        // Call the function
+        let result = Session::load(dir.path(), temp_file.path().to_str().unwrap(), 1, None, None, ShuffleMode::Full, &mut rand::thread_rng());
+
+        // Verify the result
+        assert!(result.is_ok());
+        let Session { headers, cards: rows, .. } = result.unwrap();
+
+        // Check headers
+        assert_eq!(headers, vec!["Question", "Answer"]);
+
+        // Check rows
+        assert_eq!(rows.len(), 1); // Only one random row should be returned
+        assert_eq!(rows[0].len(), 2); // Each row should have two columns
+    }
+
+    #[test]
+    fn test_load_file_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        // Call the function with an invalid file path
+        let result = Session::load(dir.path(), "invalid_path.csv", 1, None, None, ShuffleMode::Full, &mut rand::thread_rng());
+
+        // Verify that the function returns an error
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_load_empty_csv() {
+        use std::io::Write;
+        let dir = tempfile::tempdir().unwrap();
+        // Create an empty temporary CSV file
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "Question;Answer").unwrap(); // Only headers, no data
+
+        // Call the function
+        let result = Session::load(dir.path(), temp_file.path().to_str().unwrap(), 1, None, None, ShuffleMode::Full, &mut rand::thread_rng());
+
+        // Verify the result
+        assert!(result.is_ok());
+        let Session { headers, cards: rows, .. } = result.unwrap();
+
+        // Check headers
+        assert_eq!(headers, vec!["Question", "Answer"]);
+
+        // Check rows
+        assert_eq!(rows.len(), 0); // No rows should be returned
+    }
+
+    #[test]
+    fn test_load_n_larger_than_rows() {
+        use std::io::Write;
+        let dir = tempfile::tempdir().unwrap();
+        // Create a temporary CSV file with a small number of rows
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            temp_file,
+            "Question;Answer\nWhat is Rust?;A systems programming language."
+        )
+        .unwrap();
+
+        // Call the function with n larger than the number of rows
+        let result = Session::load(dir.path(), temp_file.path().to_str().unwrap(), 5, None, None, ShuffleMode::Full, &mut rand::thread_rng());
+
+        // Verify the result
+        assert!(result.is_ok());
+        let Session { headers, cards: rows, .. } = result.unwrap();
+
+        // Check headers
+        assert_eq!(headers, vec!["Question", "Answer"]);
+
+        // Check rows
+        assert_eq!(rows.len(), 1); // Only one row exists in the CSV
+    }
+
+    #[test]
+    fn test_load_semicolon_delimiter() {
+        use std::io::Write;
+        let dir = tempfile::tempdir().unwrap();
+        // Create a temporary CSV file with semicolon as the delimiter
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            temp_file,
+            "Question;Answer\nWhat is Rust?;A systems programming language.\nWhat is Cargo?;Rust's package manager."
+        )
+        .unwrap();
+
+        // Call the function
+        let result = Session::load(dir.path(), temp_file.path().to_str().unwrap(), 2, None, None, ShuffleMode::Full, &mut rand::thread_rng());
+
+        // Verify the result
+        assert!(result.is_ok());
+        let Session { headers, cards: rows, .. } = result.unwrap();
+
+        // Check headers
+        assert_eq!(headers, vec!["Question", "Answer"]);
+
+        // Check rows
+        assert_eq!(rows.len(), 2); // Two rows should be returned
+    }
+
+    #[test]
+    fn test_load_with_seed_is_repeatable() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use std::io::Write;
+        let dir = tempfile::tempdir().unwrap();
+        let mut deck = tempfile::NamedTempFile::new().unwrap();
+        writeln!(deck, "Question;Answer").unwrap();
+        for i in 0..20 {
+            writeln!(deck, "q{};a{}", i, i).unwrap();
+        }
+        let path = deck.path().to_str().unwrap();
+
+        let first = Session::load(dir.path(), path, 5, None, None, ShuffleMode::Full, &mut StdRng::seed_from_u64(7)).unwrap();
+        let second = Session::load(dir.path(), path, 5, None, None, ShuffleMode::Full, &mut StdRng::seed_from_u64(7)).unwrap();
+        assert_eq!(first.cards, second.cards);
+        assert_eq!(first.cards.len(), 5);
+
+        // Without shuffling the session starts at the top of the file
+        let ordered = Session::load(dir.path(), path, 3, None, None, ShuffleMode::None, &mut StdRng::seed_from_u64(7)).unwrap();
+        assert_eq!(ordered.cards.iter().map(|c| c[0].as_str()).collect::<Vec<_>>(), vec!["q0", "q1", "q2"]);
+    }
+
+    #[test]
+    fn test_tags_filter_and_edit() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.csv");
+        std::fs::write(&path, "Question;Tags;Answer\nWhat is TCP?;networking;A protocol\nWhat is IP?;networking basics;Addresses\nWhat is Rust?;;A language\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        // The tags column is left out of the answers and filters the cards
+        let filter: TagFilter = "networking and not basics".parse().unwrap();
+        let mut session = Session::load(dir.path(), path, 5, None, Some(&filter), ShuffleMode::None, &mut StdRng::seed_from_u64(1)).unwrap();
+        session.progress = ProgressStore::default();
+        session.log = None;
+        assert_eq!(session.headers, vec!["Question", "Answer"]);
+        assert_eq!(session.cards, vec![vec!["What is TCP?", "A protocol"]]);
+        assert_eq!(session.card_tags(0), vec!["networking"]);
+
+        // Edited tags go into their own column of the deck, undo puts the old ones back
+        session.edit_card(0, vec![String::from("What is TCP?"), String::from("A protocol")], Some(String::from("networking tcp"))).unwrap();
+        assert_eq!(session.card_tags(0), vec!["networking", "tcp"]);
+        let rows = || file_handler::read_csv_with_headers(path).unwrap().1;
+        assert_eq!(rows()[0], vec!["What is TCP?", "networking tcp", "A protocol"]);
+        session.undo();
+        assert_eq!(rows()[0], vec!["What is TCP?", "networking", "A protocol"]);
+        assert_eq!(session.tags[0], "networking");
+
+        // A deck without tags can't be filtered
+        std::fs::write(dir.path().join("plain.csv"), "Question;Answer\nq;a\n").unwrap();
+        let plain = dir.path().join("plain.csv");
+        assert!(Session::load(dir.path(), plain.to_str().unwrap(), 5, None, Some(&filter), ShuffleMode::None, &mut StdRng::seed_from_u64(1)).is_err());
+    }
+
+    #[test]
+    fn test_query_across_decks() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let dir = tempfile::tempdir().unwrap();
+        let french = dir.path().join("fr.csv");
+        let german = dir.path().join("de.csv");
+        std::fs::write(&french, "French;English\nLa maison;The house\nLe chien;The dog\n").unwrap();
+        std::fs::write(&german, "German;English;Tags\nDas Haus;The house;nouns\nlaufen;to run;verbs\n").unwrap();
+        let (french, german) = (progress::deck_key(&french), progress::deck_key(&german));
+        let mut store = ProgressStore::load(dir.path());
+        store.record(&french, "Le chien", false, progress::today());
+        store.save().unwrap();
+
+        let query: Query = "due:today or col:German -tag:verbs".parse().unwrap();
+        let decks = [french.clone(), german.clone(), String::from("missing.csv")];
+        let mut session = Session::query(dir.path(), &decks, &query, 10, ShuffleMode::None, &mut StdRng::seed_from_u64(1));
+        assert_eq!(session.cards, vec![vec!["Le chien", "The dog"], vec!["Das Haus", "The house"]]);
+        assert!(session.deck.is_empty());
+        assert_eq!(session.warnings.len(), 1);
+        assert!(session.warnings[0].starts_with("Error reading missing.csv"));
+
+        // Every card keeps the headers, tags and deck it came from
+        assert_eq!(session.card_headers(1), ["German", "English"]);
+        assert_eq!(session.card_tags(1), vec!["nouns"]);
+        assert_eq!(session.card_deck(0), french);
+        session.grade(true);
+        session.grade(false);
+        assert_eq!(session.progress.get(&french, "Le chien").unwrap().reps, 2);
+        assert_eq!(session.progress.get(&german, "Das Haus").unwrap().lapses, 1);
+        assert_eq!(session.reviews[1].deck, german);
+
+        // Missed cards keep their deck too, and every card of the decks read was noted as added today
+        let retry = session.retry_missed();
+        assert_eq!(retry.card_headers(0), ["German", "English"]);
+        let added = AddedStore::load(dir.path());
+        assert_eq!(added.get(&german, "laufen"), Some(progress::today()));
+    }
+
+    #[test]
+    fn test_load_several_decks() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let dir = tempfile::tempdir().unwrap();
+        let french = dir.path().join("fr.csv");
+        let german = dir.path().join("de.csv");
+        std::fs::write(&french, "French;English\nLa maison;The house\n").unwrap();
+        std::fs::write(&german, "German;Tags;English\nDas Haus;nouns;The house\nlaufen;verbs;to run\n").unwrap();
+        let decks = [progress::deck_key(&french), progress::deck_key(&german)];
+
+        let session = Session::load_decks(dir.path(), &decks, 10, None, ShuffleMode::None, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(session.deck_count(), 2);
+        assert!(session.deck.is_empty());
+        assert_eq!(session.cards.len(), 3);
+        assert_eq!(session.card_headers(0), ["French", "English"]);
+        assert_eq!(session.card_headers(2), ["German", "English"]);
+        assert_eq!(session.card_deck(2), decks[1]);
+
+        // A tag filter leaves out the decks without tags, and says so
+        let filter: TagFilter = "nouns".parse().unwrap();
+        let session = Session::load_decks(dir.path(), &decks, 10, Some(&filter), ShuffleMode::None, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(session.cards, vec![vec!["Das Haus", "The house"]]);
+        assert_eq!(session.warnings, vec![format!("{} has no tags column to filter by", decks[0])]);
+
+        // Reading a deck notes its cards as added, also the ones the filter leaves out
+        let added = AddedStore::load(dir.path());
+        assert_eq!(added.get(&decks[0], "La maison"), Some(progress::today()));
+        assert_eq!(added.get(&decks[1], "laufen"), Some(progress::today()));
+
+        // One deck is just a normal session, a missing one is an error
+        assert_eq!(Session::load_decks(dir.path(), &decks[..1], 10, None, ShuffleMode::None, &mut StdRng::seed_from_u64(1)).unwrap().deck, decks[0]);
+        assert!(Session::load_decks(dir.path(), &[String::from("missing.csv"), decks[0].clone()], 10, None, ShuffleMode::None, &mut StdRng::seed_from_u64(1)).is_err());
+    }
+
+    #[test]
+    fn test_card_status_of_decks_sharing_a_question() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.csv"), "Question;Answer\nWhat is it?;A house\n").unwrap();
+        std::fs::write(dir.path().join("b.csv"), "Question;Answer\nWhat is it?;A dog\n").unwrap();
+        let decks = [progress::deck_key(&dir.path().join("a.csv")), progress::deck_key(&dir.path().join("b.csv"))];
+
+        let mut session = Session::load_decks(dir.path(), &decks, 10, None, ShuffleMode::None, &mut StdRng::seed_from_u64(1)).unwrap();
+        session.grade(false);
+        assert_eq!(session.card_status(0), CardStatus::Failed);
+        assert_eq!(session.card_status(1), CardStatus::Unseen);
+    }
+
+    #[test]
+    fn test_deck_metadata() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path().join("fr.csv");
+        std::fs::write(&deck, "#name: French basics\n#direction: 1:0\n#language.French: fr\n#retry_after: 1\nFrench;English\nLa maison;The house\n").unwrap();
+        let deck = progress::deck_key(&deck);
+
+        // The deck's direction and settings are used unless the caller picks others
+        let mut session = Session::load(dir.path(), &deck, 10, None, None, ShuffleMode::None, &mut StdRng::seed_from_u64(1)).unwrap();
+        session.configure(3, 8, false);
+        assert_eq!(session.cards, vec![vec!["The house", "La maison"]]);
+        assert_eq!(session.card_labels(0), ["English", "French (fr)"]);
+        assert_eq!(session.deck_name(0).as_deref(), Some("French basics"));
+        assert_eq!((session.retry_after, session.leech_lapses), (1, 8));
+
+        let session = Session::load(dir.path(), &deck, 10, Some(&[0, 1]), None, ShuffleMode::None, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(session.cards, vec![vec!["La maison", "The house"]]);
+    }
+
+    #[test]
+    fn test_load_in_buckets() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.csv");
+        std::fs::write(&path, "Question;Answer\nlearned;a\nnew;a\nmissed;a\nlearning;a\n").unwrap();
+        let deck = progress::deck_key(&path);
+        let mut store = ProgressStore::load(dir.path());
+        store.record(&deck, "missed", false, 0);
+        store.record(&deck, "learning", true, 0);
+        store.record(&deck, "learned", true, 0);
+        for _ in 0..5 {
+            store.record(&deck, "learned", true, 0);
+        }
+        store.save().unwrap();
+
+        let session = Session::load(dir.path(), path.to_str().unwrap(), 5, None, None, ShuffleMode::Buckets, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(session.cards.iter().map(|card| card[0].as_str()).collect::<Vec<_>>(), vec!["missed", "new", "learning", "learned"]);
+        assert_eq!("buckets".parse::<ShuffleMode>(), Ok(ShuffleMode::Buckets));
+        assert!("sideways".parse::<ShuffleMode>().is_err());
+    }
+
+    #[test]
+    fn test_advance_stops_at_the_end() {
+        let mut session = session();
+        session.advance();
+        assert!(!session.is_finished());
+        session.advance();
+        assert!(session.is_finished());
+        session.advance();
+        assert_eq!(session.current, 2);
+        assert!(session.current_card().is_none());
+
+        // An empty session stays put
+        let mut empty = Session::new(vec![], vec![]);
+        empty.advance();
+        empty.grade(true);
+        assert_eq!(empty.current, 0);
+        assert!(!empty.is_finished());
+    }
+
+    #[test]
+    fn test_summary_and_retry_missed() {
+        let mut session = session();
+        session.grade(false);
+        session.grade(true);
+        // The missed card came back and was missed again
+        session.grade(false);
+        assert!(session.is_repeat());
+        session.advance();
+        assert!(session.is_finished());
+
+        let summary = session.summary();
+        assert_eq!((summary.seen, summary.graded, summary.correct), (2, 3, 1));
+        assert_eq!(summary.correct_rate(), Some(100.0 / 3.0));
+        assert_eq!(summary.hardest, vec!["What is Rust?", "What is Cargo?"]);
+
+        let retry = session.retry_missed();
+        assert_eq!(retry.cards, vec![vec!["What is Rust?", "A language"]]);
+        assert!(!retry.is_finished());
+        assert_eq!(retry.progress.get("", "What is Rust?").unwrap().lapses, 2);
+    }
+
+    #[test]
+    fn test_missed_cards_come_back() {
+        let cards = (0..6).map(|i| vec![format!("q{}", i), format!("a{}", i)]).collect();
+        let mut long = Session::new(vec![String::from("Question"), String::from("Answer")], cards);
+        let mut order = Vec::new();
+        while let Some(card) = long.current_card() {
+            order.push(card[0].clone());
+            // Miss q0 once, it comes back after three other cards
+            let missed = card[0] == "q0" && !long.is_repeat();
+            long.grade(!missed);
+        }
+        assert_eq!(order, vec!["q0", "q1", "q2", "q3", "q0", "q4", "q5"]);
+        assert_eq!(long.remaining(), 0);
+
+        // Near the end of the queue it comes back right after the last card, with 0 it doesn't at all
+        let mut session = session();
+        session.advance();
+        session.grade(false);
+        assert_eq!(session.current, 1);
+        assert!(session.is_repeat());
+        let mut session = Session { retry_after: 0, ..self::session() };
+        session.grade(false);
+        session.grade(true);
+        assert!(session.is_finished());
+    }
+
+    #[test]
+    fn test_leeches_are_flagged_and_suspended() {
+        let mut session = Session { leech_lapses: 2, ..session() };
+        session.grade(false);
+        session.grade(true);
+        session.grade(false);
+        assert_eq!(session.leeches, vec!["What is Rust?"]);
+        assert!(!session.progress.get("", "What is Rust?").unwrap().suspended);
+        // Without suspending it keeps coming back
+        assert!(!session.is_finished());
+
+        let mut session = Session { leech_lapses: 2, suspend_leeches: true, ..session.with_cards(vec![vec![String::from("What is Cargo?"), String::from("A tool")]]) };
+        session.grade(false);
+        session.grade(false);
+        assert!(session.is_finished());
+        assert!(session.progress.get("", "What is Cargo?").unwrap().suspended);
+        assert_eq!(session.summary().leeches, vec!["What is Cargo?"]);
+        assert!(session.missed_cards().is_empty());
+    }
+
+    #[test]
+    fn test_edit_card() {
+        let mut session = session();
+        session.grade(false);
+        session.edit_card(0, vec![String::from("What is Rust, really?"), String::from("A language")], None).unwrap();
+        assert_eq!(session.cards[0][0], "What is Rust, really?");
+        assert_eq!(session.progress.get("", "What is Rust, really?").unwrap().lapses, 1);
+        assert!(session.progress.get("", "What is Rust?").is_none());
+        assert!(session.edit_card(5, vec![], None).is_err());
+    }
+
+    #[test]
+    fn test_failed_edit_changes_nothing() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.csv");
+        std::fs::write(&path, "Question;Answer\nWhat is Rust?;A language\n").unwrap();
+        let mut session = Session::load(&dir.path().join("data"), path.to_str().unwrap(), 5, None, None, ShuffleMode::None, &mut StdRng::seed_from_u64(1)).unwrap();
+        let edited = || vec![String::from("What's Rust?"), String::from("A language")];
+
+        // The card is gone from the deck file, its progress stays under the old question
+        std::fs::write(&path, "Question;Answer\n").unwrap();
+        session.grade(false);
+        assert!(session.edit_card(0, edited(), None).is_err());
+        assert_eq!(session.cards[0][0], "What is Rust?");
+        assert_eq!(ProgressStore::load(&dir.path().join("data")).get(&session.deck, "What is Rust?").unwrap().lapses, 1);
+        assert!(session.progress.get(&session.deck, "What's Rust?").is_none());
+        assert_eq!(session.undo.len(), 1, "Only the grade can be undone");
+
+        // The progress can't be saved, the deck file is left alone
+        std::fs::write(&path, "Question;Answer\nWhat is Rust?;A language\n").unwrap();
+        session.progress = ProgressStore::load(&path.join("data"));
+        assert!(session.edit_card(0, edited(), None).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Question;Answer\nWhat is Rust?;A language\n");
+        assert_eq!(session.cards[0][0], "What is Rust?");
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let mut session = session();
+        session.grade(true);
+        session.advance();
+        assert!(session.is_finished());
+
+        // Undo the skip and the grade, the progress is gone again
+        assert!(session.undo());
+        assert_eq!(session.current, 1);
+        assert!(session.undo());
+        assert_eq!(session.current, 0);
+        assert!(session.reviews.is_empty());
+        assert!(session.progress.get("", "What is Rust?").is_none());
+        assert!(!session.undo());
+
+        // Redo puts it back, a new action drops what is left to redo
+        assert!(session.redo());
+        assert_eq!(session.progress.get("", "What is Rust?").unwrap().reps, 1);
+        session.grade(false);
+        assert!(!session.redo());
+
+        // An edited question gets its old text and progress back
+        session.edit_card(0, vec![String::from("What's Rust?"), String::from("A language")], None).unwrap();
+        session.undo();
+        assert_eq!(session.cards[0][0], "What is Rust?");
+        assert!(session.progress.get("", "What is Rust?").is_some());
+        assert!(session.progress.get("", "What's Rust?").is_none());
+    }
+
+    #[test]
+    fn test_undo_that_cant_write_the_deck_stops() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deck.csv");
+        std::fs::write(&path, "Question;Answer\nWhat is Rust?;A language\n").unwrap();
+        let mut session = Session::load(&dir.path().join("data"), path.to_str().unwrap(), 5, None, None, ShuffleMode::None, &mut StdRng::seed_from_u64(1)).unwrap();
+        session.edit_card(0, vec![String::from("What's Rust?"), String::from("A language")], None).unwrap();
+
+        // Someone else rewrote the card in the meantime
+        std::fs::write(&path, "Question;Answer\nWhat is Rust?;A language\n").unwrap();
+        assert!(!session.undo());
+        assert_eq!(session.cards[0][0], "What's Rust?");
+        assert!(session.warnings[0].starts_with("Error restoring card"));
+        assert_eq!((session.undo.len(), session.redo.len()), (1, 0));
+
+        std::fs::write(&path, "Question;Answer\nWhat's Rust?;A language\n").unwrap();
+        assert!(session.undo());
+        assert_eq!(session.cards[0][0], "What is Rust?");
+    }
+
+    #[test]
+    fn test_undo_only_takes_its_own_reviews_out_of_the_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = reviews::log_path(dir.path());
+        let mut session = Session { log: Some(log.clone()), ..session() };
+        session.grade(true);
+
+        // Another session answered in between
+        let other = Review { deck: String::from("other.csv"), card: String::from("Le chat"), day: 1, correct: false, millis: 10 };
+        reviews::append(&log, &other).unwrap();
+        session.undo();
+        assert_eq!(reviews::load(&log), vec![other]);
+    }
+
+    #[test]
+    fn test_go_to_and_previous() {
+        let cards = (0..4).map(|i| vec![format!("q{}", i), format!("a{}", i)]).collect();
+        let mut session = Session::new(vec![String::from("Question"), String::from("Answer")], cards);
+        session.grade(true);
+        session.grade(false);
+
+        // Jump ahead, the skipped card still comes up afterwards
+        assert!(session.go_to(3));
+        assert_eq!(session.card_status(2), CardStatus::Unseen);
+        session.advance();
+        assert_eq!(session.current, 2);
+        assert!(!session.is_repeat());
+        assert_eq!(session.card_status(3), CardStatus::Seen);
+
+        // Back through the cards in the order they were shown, to the missed card
+        assert!(session.previous());
+        assert_eq!(session.current, 3);
+        assert!(session.previous());
+        assert_eq!(session.current, 2);
+        assert!(session.previous());
+        assert_eq!(session.current, 1);
+        assert!(session.is_repeat());
+        assert_eq!(session.card_status(0), CardStatus::Correct);
+        assert_eq!(session.card_status(1), CardStatus::Failed);
+        assert!(!session.go_to(4));
+
+        // Stepping back is undone like everything else
+        session.undo();
+        assert_eq!(session.current, 2);
+    }
+
+    #[test]
+    fn test_previous_from_a_card_that_came_back() {
+        let mut session = self::session();
+        session.grade(false);
+        session.grade(true);
+        assert_eq!(session.current, 0);
+        assert!(session.is_repeat());
+
+        // The card before the missed one coming back is the one shown last, not the one above it in the deck
+        assert!(session.previous());
+        assert_eq!(session.current, 1);
+        assert!(session.previous());
+        assert_eq!(session.current, 0);
+        assert!(!session.previous());
+    }
+}
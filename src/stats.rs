@@ -0,0 +1,141 @@
+use std::path::Path;
+use crate::progress::{self, ProgressStore};
+use crate::reviews::{self, Review};
+use crate::scheduler::LEARNED_INTERVAL;
+
+// Weeks shown in the review heatmap
+pub const HEATMAP_WEEKS: u64 = 16;
+// Days looked back for retention and answer times, and ahead for the forecast
+pub const RECENT_DAYS: u64 = 30;
+// Cards below this interval are still learning, above it they are young until they count as learned (mature)
+const YOUNG_INTERVAL: u32 = 7;
+
+// Number of cards in each learning stage
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Maturity {
+    pub new: u64,
+    pub learning: u64,
+    pub young: u64,
+    pub mature: u64,
+}
+
+// Everything the stats screen shows, computed once from the review log and the progress store
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Dashboard {
+    pub today: u64,
+    pub heatmap_start: u64,        // A Monday, HEATMAP_WEEKS weeks back
+    pub reviews_per_day: Vec<u64>, // Reviews on each day from heatmap_start up to today
+    pub retention: Vec<(f64, f64)>, // (days before today as a negative number, % correct) for days with reviews
+    pub overall_retention: Option<f64>,
+    pub maturity: Maturity,
+    pub average_answer_millis: Option<u64>,
+    pub answer_millis_per_day: Vec<u64>, // Average answer time of the last RECENT_DAYS days, 0 without reviews
+    pub forecast: Vec<u64>,        // Cards due on each of the next RECENT_DAYS days, overdue ones count for today
+    pub total_reviews: usize,
+}
+
+impl Dashboard {
+    // `new_cards` are the cards in the library that were never studied, the progress store doesn't know them
+    pub fn load(data_dir: &Path, new_cards: u64) -> Self {
+        let reviews = reviews::load(&reviews::log_path(data_dir));
+        Self::new(&reviews, &ProgressStore::load(data_dir), new_cards, progress::today())
+    }
+
+    pub fn new(reviews: &[Review], store: &ProgressStore, new_cards: u64, today: u64) -> Self {
+        let heatmap_start = (today - weekday(today)).saturating_sub(7 * (HEATMAP_WEEKS - 1));
+        let recent_start = today.saturating_sub(RECENT_DAYS - 1);
+
+        let mut reviews_per_day = vec![0; (today - heatmap_start + 1) as usize];
+        for review in reviews.iter().filter(|r| r.day >= heatmap_start && r.day <= today) {
+            reviews_per_day[(review.day - heatmap_start) as usize] += 1;
+        }
+
+        // Correct answers, answers and total answer time of each recent day
+        let mut recent = vec![(0u64, 0u64, 0u64); RECENT_DAYS as usize];
+        for review in reviews.iter().filter(|r| r.day >= recent_start && r.day <= today) {
+            let day = &mut recent[(review.day - recent_start) as usize];
+            day.0 += review.correct as u64;
+            day.1 += 1;
+            day.2 += review.millis;
+        }
+        let retention = recent.iter().enumerate()
+            .filter(|(_, (_, count, _))| *count > 0)
+            .map(|(i, (correct, count, _))| (i as f64 - (RECENT_DAYS - 1) as f64, percent(*correct, *count)))
+            .collect();
+        let answer_millis_per_day = recent.iter().map(|(_, count, millis)| if *count > 0 { millis / count } else { 0 }).collect();
+
+        let correct = reviews.iter().filter(|r| r.correct).count() as u64;
+        let count = reviews.len() as u64;
+        let total_millis: u64 = reviews.iter().map(|r| r.millis).sum();
+
+        let mut maturity = Maturity { new: new_cards, ..Maturity::default() };
+        let mut forecast = vec![0; RECENT_DAYS as usize];
+        for (_, _, p) in store.iter() {
+            match p.interval {
+                i if i >= LEARNED_INTERVAL => maturity.mature += 1,
+                i if i >= YOUNG_INTERVAL => maturity.young += 1,
+                _ => maturity.learning += 1,
+            }
+            let day = p.due.saturating_sub(today);
+            if day < RECENT_DAYS && !p.suspended {
+                forecast[day as usize] += 1;
+            }
+        }
+
+        Dashboard {
+            today,
+            heatmap_start,
+            reviews_per_day,
+            retention,
+            overall_retention: (count > 0).then(|| percent(correct, count)),
+            maturity,
+            average_answer_millis: (count > 0).then(|| total_millis / count),
+            answer_millis_per_day,
+            forecast,
+            total_reviews: reviews.len(),
+        }
+    }
+}
+
+// Day of the week with Monday as 0, the epoch was a Thursday
+pub fn weekday(day: u64) -> u64 {
+    (day + 3) % 7
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    part as f64 * 100.0 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dashboard() {
+        let today = 20_000; // A Friday
+        let review = |day, correct, millis| Review { deck: String::from("deck"), card: String::from("card"), day, correct, millis };
+        let reviews = vec![review(today - 1, true, 1000), review(today - 1, false, 3000), review(today, true, 2000), review(today - 400, true, 500)];
+
+        let mut store = ProgressStore::default();
+        store.record("deck", "missed", false, today);
+        store.record("deck", "known", true, today);
+        store.record("deck", "learned", true, today - 100);
+        for _ in 0..5 {
+            store.record("deck", "learned", true, today - 100);
+        }
+
+        let dashboard = Dashboard::new(&reviews, &store, 3, today);
+        assert_eq!(weekday(dashboard.heatmap_start), 0);
+        assert_eq!(*dashboard.reviews_per_day.last().unwrap(), 1);
+        assert_eq!(dashboard.reviews_per_day[dashboard.reviews_per_day.len() - 2], 2);
+        assert_eq!(dashboard.retention, vec![(-1.0, 50.0), (0.0, 100.0)]);
+        assert_eq!(dashboard.overall_retention, Some(75.0));
+        assert_eq!(dashboard.average_answer_millis, Some(1625));
+        assert_eq!(dashboard.answer_millis_per_day[RECENT_DAYS as usize - 2], 2000);
+        assert_eq!(dashboard.maturity, Maturity { new: 3, learning: 2, young: 0, mature: 1 });
+
+        // The missed card is due today, the known one tomorrow, the learned one is long overdue
+        assert_eq!(dashboard.forecast[0], 2);
+        assert_eq!(dashboard.forecast[1], 1);
+    }
+}
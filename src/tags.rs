@@ -0,0 +1,181 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Name of the optional deck column holding the tags of each card
+pub const TAGS_COLUMN: &str = "tags";
+
+// Index of the tags column of a deck, if it has one
+pub fn tags_column(headers: &[String]) -> Option<usize> {
+    headers.iter().position(|header| header.trim().eq_ignore_ascii_case(TAGS_COLUMN))
+}
+
+// "networking, verbs" or "networking verbs" -> ["networking", "verbs"]
+pub fn parse_tags(text: &str) -> Vec<String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+// Which tags a card needs to be studied, e.g. "networking and not basics".
+// "not" binds tighter than "and", which binds tighter than "or", parentheses group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagFilter {
+    Tag(String),
+    Not(Box<TagFilter>),
+    And(Box<TagFilter>, Box<TagFilter>),
+    Or(Box<TagFilter>, Box<TagFilter>),
+}
+
+impl TagFilter {
+    // Tags are compared without case
+    pub fn matches(&self, tags: &[String]) -> bool {
+        match self {
+            TagFilter::Tag(tag) => tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            TagFilter::Not(filter) => !filter.matches(tags),
+            TagFilter::And(left, right) => left.matches(tags) && right.matches(tags),
+            TagFilter::Or(left, right) => left.matches(tags) || right.matches(tags),
+        }
+    }
+}
+
+impl FromStr for TagFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let spaced = s.replace('(', " ( ").replace(')', " ) ");
+        let tokens: Vec<&str> = spaced.split_whitespace().collect();
+        if tokens.is_empty() {
+            return Err(String::from("the tag filter is empty"));
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let filter = parser.or()?;
+        match parser.peek() {
+            None => Ok(filter),
+            Some(token) => Err(format!("unexpected {:?} in the tag filter", token)),
+        }
+    }
+}
+
+// Written back in the same syntax, with parentheses only where they are needed
+impl fmt::Display for TagFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let grouped = |filter: &TagFilter| match filter {
+            TagFilter::Or(..) => format!("({})", filter),
+            _ => filter.to_string(),
+        };
+        match self {
+            TagFilter::Tag(tag) => f.write_str(tag),
+            TagFilter::Not(filter) => match **filter {
+                TagFilter::And(..) => write!(f, "not ({})", filter),
+                _ => write!(f, "not {}", grouped(filter)),
+            },
+            TagFilter::And(left, right) => write!(f, "{} and {}", grouped(left), grouped(right)),
+            TagFilter::Or(left, right) => write!(f, "{} or {}", left, right),
+        }
+    }
+}
+
+// Recursive descent over the words of a filter
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    // Take the next token if it is the given keyword
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek().map(|token| token.eq_ignore_ascii_case(keyword)).unwrap_or(false);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<TagFilter, String> {
+        let mut filter = self.and()?;
+        while self.keyword("or") {
+            filter = TagFilter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<TagFilter, String> {
+        let mut filter = self.not()?;
+        while self.keyword("and") {
+            filter = TagFilter::And(Box::new(filter), Box::new(self.not()?));
+        }
+        Ok(filter)
+    }
+
+    fn not(&mut self) -> Result<TagFilter, String> {
+        if self.keyword("not") {
+            return Ok(TagFilter::Not(Box::new(self.not()?)));
+        }
+        match self.peek() {
+            Some("(") => {
+                self.pos += 1;
+                let filter = self.or()?;
+                if self.peek() != Some(")") {
+                    return Err(String::from("missing ')' in the tag filter"));
+                }
+                self.pos += 1;
+                Ok(filter)
+            }
+            Some(")") => Err(String::from("unexpected ')' in the tag filter")),
+            Some(token) if ["and", "or"].iter().any(|k| token.eq_ignore_ascii_case(k)) => {
+                Err(format!("expected a tag before {:?}", token))
+            }
+            Some(token) => {
+                self.pos += 1;
+                Ok(TagFilter::Tag(token.to_string()))
+            }
+            None => Err(String::from("the tag filter ends too early")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(text: &str) -> Vec<String> {
+        parse_tags(text)
+    }
+
+    #[test]
+    fn test_parse_tags_and_column() {
+        assert_eq!(tags("networking, verbs  basics"), vec!["networking", "verbs", "basics"]);
+        assert!(tags(" ").is_empty());
+        assert_eq!(tags_column(&[String::from("Question"), String::from("Answer"), String::from(" Tags")]), Some(2));
+        assert_eq!(tags_column(&[String::from("Question"), String::from("Answer")]), None);
+    }
+
+    #[test]
+    fn test_filter_expressions() {
+        let filter: TagFilter = "networking and not basics".parse().unwrap();
+        assert!(filter.matches(&tags("networking tcp")));
+        assert!(!filter.matches(&tags("Networking basics")));
+        assert!(!filter.matches(&tags("")));
+
+        // "and" binds tighter than "or", parentheses change that
+        let filter: TagFilter = "verbs or nouns and not basics".parse().unwrap();
+        assert!(filter.matches(&tags("verbs basics")));
+        let filter: TagFilter = "(verbs or nouns) and not basics".parse().unwrap();
+        assert!(!filter.matches(&tags("verbs basics")));
+        assert!(filter.matches(&tags("nouns")));
+        assert_eq!(filter.to_string(), "(verbs or nouns) and not basics");
+        assert_eq!("not (a and b)".parse::<TagFilter>().unwrap().to_string(), "not (a and b)");
+    }
+
+    #[test]
+    fn test_invalid_filters() {
+        for filter in ["", "networking and", "and basics", "(verbs or nouns", "verbs)", "verbs nouns"] {
+            assert!(filter.parse::<TagFilter>().is_err(), "{:?} should not parse", filter);
+        }
+    }
+}